@@ -0,0 +1,216 @@
+// Automatic offset detection by aligning speech intervals against a reference
+
+use crate::subtitle::SubtitleDocument;
+use crate::sync::two_point_map;
+use crate::timing::{LinearMap, Ratio};
+
+/// Options for [`align`].
+#[derive(Debug, Clone)]
+pub struct AlignOptions {
+    /// Largest offset searched in either direction.
+    pub max_offset_ms: i64,
+    /// Also try the usual framerate conversion factors (25/23.976, 24/25, ...).
+    pub detect_scale: bool,
+    /// Refine the result with a free stretch fitted from the offsets of the
+    /// first and second half of the file, for slow drift of any amount.
+    pub detect_drift: bool,
+}
+
+impl Default for AlignOptions {
+    fn default() -> Self {
+        AlignOptions { max_offset_ms: 600_000, detect_scale: false, detect_drift: false }
+    }
+}
+
+/// Result of aligning a subtitle against a reference.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Alignment {
+    /// Added after scaling, like `shift_ms`.
+    pub offset_ms: i64,
+    pub scale: Ratio,
+    /// Correlation of the aligned speech patterns, from 0 (unrelated) to 1 (identical).
+    pub confidence: f64,
+}
+
+impl Alignment {
+    pub fn to_linear_map(&self) -> LinearMap {
+        LinearMap::new(self.scale, self.offset_ms)
+    }
+}
+
+/// Scales tried with `detect_scale`, as (subtitle fps, video fps) pairs.
+const FPS_PAIRS: [(i64, i64, i64, i64); 6] = [
+    (25, 1, 24000, 1001),
+    (24000, 1001, 25, 1),
+    (25, 1, 24, 1),
+    (24, 1, 25, 1),
+    (24, 1, 24000, 1001),
+    (24000, 1001, 24, 1),
+];
+
+/// Merges cue times into sorted, non-overlapping speech intervals.
+pub fn speech_intervals(doc: &SubtitleDocument) -> Vec<(i64, i64)> {
+    merge_intervals(doc.cues().map(|cue| (cue.start, cue.end)).collect())
+}
+
+/// Sorts intervals and merges the ones that overlap or touch. Empty intervals are dropped.
+pub fn merge_intervals(mut intervals: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    intervals.retain(|(start, end)| end > start);
+    intervals.sort();
+    let mut merged: Vec<(i64, i64)> = Vec::with_capacity(intervals.len());
+    for (start, end) in intervals {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Speech time shared by two sets of merged intervals, with `other` shifted by `offset`.
+fn overlap(reference: &[(i64, i64)], other: &[(i64, i64)], offset: i64) -> i64 {
+    let (mut i, mut j) = (0, 0);
+    let mut shared = 0;
+    while i < reference.len() && j < other.len() {
+        let (ref_start, ref_end) = reference[i];
+        let (start, end) = (other[j].0 + offset, other[j].1 + offset);
+        shared += (ref_end.min(end) - ref_start.max(start)).max(0);
+        if ref_end < end {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    shared
+}
+
+fn total(intervals: &[(i64, i64)]) -> i64 {
+    intervals.iter().map(|(start, end)| end - start).sum()
+}
+
+/// Best offset for `target` against `reference`: a coarse scan, then a
+/// millisecond-precise search around the peak.
+fn best_offset(reference: &[(i64, i64)], target: &[(i64, i64)], max_offset_ms: i64) -> (i64, i64) {
+    const COARSE_STEP: i64 = 100;
+    let mut best: (i64, i64) = (i64::MIN, 0);
+    
+    let mut offset = -max_offset_ms;
+    while offset <= max_offset_ms {
+        let score = overlap(reference, target, offset);
+        if score > best.0 || (score == best.0 && offset.abs() < best.1.abs()) {
+            best = (score, offset);
+        }
+        offset += COARSE_STEP;
+    }
+    
+    let center = best.1;
+    for offset in (center - COARSE_STEP)..=(center + COARSE_STEP) {
+        let score = overlap(reference, target, offset);
+        if score > best.0 || (score == best.0 && (offset - center).abs() < (best.1 - center).abs()) {
+            best = (score, offset);
+        }
+    }
+    
+    (best.1, best.0)
+}
+
+/// Pearson correlation of two on/off speech signals over `span` milliseconds.
+fn correlation(overlap: i64, reference_total: i64, target_total: i64, span: i64) -> f64 {
+    if span <= 0 {
+        return 0.0;
+    }
+    let span = span as f64;
+    let p_ref = reference_total as f64 / span;
+    let p_target = target_total as f64 / span;
+    let variance = p_ref * (1.0 - p_ref) * p_target * (1.0 - p_target);
+    if variance <= 0.0 {
+        return 0.0;
+    }
+    ((overlap as f64 / span - p_ref * p_target) / variance.sqrt()).clamp(0.0, 1.0)
+}
+
+/// Finds the offset (and optionally scale) that best lines up the `target`
+/// speech intervals with the `reference` ones by cross-correlating them.
+///
+/// Returns `None` if either side has no speech.
+pub fn align(reference: &[(i64, i64)], target: &[(i64, i64)], options: &AlignOptions) -> Option<Alignment> {
+    let reference = merge_intervals(reference.to_vec());
+    let target = merge_intervals(target.to_vec());
+    if reference.is_empty() || target.is_empty() {
+        return None;
+    }
+    
+    let mut scales = vec![Ratio::ONE];
+    if options.detect_scale {
+        scales.extend(FPS_PAIRS.iter().filter_map(|&(a, b, c, d)| Ratio::new(a * d, b * c)));
+    }
+    
+    let reference_total = total(&reference);
+    let mut best: Option<Alignment> = None;
+    
+    for scale in scales {
+        let scaled: Vec<(i64, i64)> = target.iter().map(|&(start, end)| (scale.apply(start), scale.apply(end))).collect();
+        let (offset_ms, overlap) = best_offset(&reference, &scaled, options.max_offset_ms);
+        
+        let span = reference.last().unwrap().1.max(scaled.last().unwrap().1 + offset_ms)
+            - reference[0].0.min(scaled[0].0 + offset_ms);
+        let confidence = correlation(overlap, reference_total, total(&scaled), span);
+        
+        if best.is_none_or(|b| confidence > b.confidence) {
+            best = Some(Alignment { offset_ms, scale, confidence });
+        }
+    }
+    
+    match best {
+        Some(alignment) if options.detect_drift => Some(refine_drift(&reference, reference_total, &target, alignment)),
+        best => best,
+    }
+}
+
+/// Largest extra correction searched for each half when refining drift.
+const DRIFT_WINDOW_MS: i64 = 5_000;
+
+/// Aligns each half of the already aligned `target` on its own and, if their
+/// offsets differ, bends the alignment through both so the drift is removed.
+fn refine_drift(reference: &[(i64, i64)], reference_total: i64, target: &[(i64, i64)], base: Alignment) -> Alignment {
+    let base_map = base.to_linear_map();
+    let mapped: Vec<(i64, i64)> = target.iter().map(|&(start, end)| (base_map.apply(start), base_map.apply(end))).collect();
+    if mapped.len() < 4 {
+        return base;
+    }
+    
+    let (first, second) = mapped.split_at(mapped.len() / 2);
+    let middle = |half: &[(i64, i64)]| (half[0].0 + half[half.len() - 1].1) / 2;
+    let (first_offset, _) = best_offset(reference, first, DRIFT_WINDOW_MS);
+    let (second_offset, _) = best_offset(reference, second, DRIFT_WINDOW_MS);
+    if first_offset == second_offset {
+        return base;
+    }
+    
+    let (first_mid, second_mid) = (middle(first), middle(second));
+    let Ok(bend) = two_point_map((first_mid, first_mid + first_offset), (second_mid, second_mid + second_offset)) else {
+        return base;
+    };
+    let Some(scale) = base.scale.checked_mul(bend.scale) else {
+        return base;
+    };
+    let candidate = LinearMap::new(scale, bend.apply(base_map.apply(0)));
+    
+    let score = |map: LinearMap| {
+        let mapped: Vec<(i64, i64)> = target.iter().map(|&(start, end)| (map.apply(start), map.apply(end))).collect();
+        let span = reference[reference.len() - 1].1.max(mapped[mapped.len() - 1].1) - reference[0].0.min(mapped[0].0);
+        correlation(overlap(reference, &mapped, 0), reference_total, total(&mapped), span)
+    };
+    let confidence = score(candidate);
+    if confidence <= score(base_map) {
+        return base;
+    }
+    Alignment { offset_ms: candidate.target, scale, confidence }
+}
+
+impl SubtitleDocument {
+    /// Aligns this document's cues against a correctly timed `reference` document.
+    pub fn align_to(&self, reference: &SubtitleDocument, options: &AlignOptions) -> Option<Alignment> {
+        align(&speech_intervals(reference), &speech_intervals(self), options)
+    }
+}
@@ -0,0 +1,95 @@
+// Advanced SubStation Alpha (.ass) parsing and serialization
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::subtitle::{AssEvent, Block, Cue, CueMeta, SubtitleDocument, SubtitleFormat, TextLayout};
+use crate::timestamp::{format_timestamp_ass, parse_timestamp_ass};
+
+static DIALOGUE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^Dialogue: (\d+),(\d+:\d+:\d+\.\d+),(\d+:\d+:\d+\.\d+),(.+)$").unwrap()
+});
+
+fn parse_dialogue(line: &str) -> Option<Cue> {
+    let caps = DIALOGUE_RE.captures(line)?;
+    let start = parse_timestamp_ass(&caps[2])?;
+    let end = parse_timestamp_ass(&caps[3])?;
+    
+    // Style,Name,MarginL,MarginR,MarginV,Effect,Text - the text may contain commas
+    let fields: Vec<&str> = caps[4].splitn(7, ',').collect();
+    if fields.len() != 7 {
+        return None;
+    }
+    
+    let mut cue = Cue {
+        start,
+        end,
+        text: fields[6].replace("\\N", "\n"),
+        meta: CueMeta::Ass(AssEvent {
+            layer: caps[1].to_string(),
+            style: fields[0].to_string(),
+            name: fields[1].to_string(),
+            margin_l: fields[2].to_string(),
+            margin_r: fields[3].to_string(),
+            margin_v: fields[4].to_string(),
+            effect: fields[5].to_string(),
+        }),
+        source_line: None,
+    };
+    cue.keep_source_line(dialogue_line(&cue), line);
+    Some(cue)
+}
+
+/// Parses ASS content into a [`SubtitleDocument`].
+///
+/// Only `Dialogue:` events become cues; the script header, styles and
+/// everything else are kept verbatim.
+pub fn parse(content: &str) -> SubtitleDocument {
+    let (layout, lines) = TextLayout::split(content);
+    let blocks = lines
+        .into_iter()
+        .map(|line| match parse_dialogue(line) {
+            Some(cue) => Block::Cue(cue),
+            None => Block::Raw(line.to_string()),
+        })
+        .collect();
+    
+    SubtitleDocument { format: SubtitleFormat::Ass, blocks, layout }
+}
+
+fn dialogue_line(cue: &Cue) -> String {
+    let default_event = AssEvent { layer: "0".to_string(), ..AssEvent::default() };
+    let event = match &cue.meta {
+        CueMeta::Ass(event) => event,
+        _ => &default_event,
+    };
+    format!(
+        "Dialogue: {},{},{},{},{},{},{},{},{},{}",
+        event.layer,
+        format_timestamp_ass(cue.start),
+        format_timestamp_ass(cue.end),
+        event.style,
+        event.name,
+        event.margin_l,
+        event.margin_r,
+        event.margin_v,
+        event.effect,
+        cue.text.replace('\n', "\\N"),
+    )
+}
+
+/// Serializes a document as ASS.
+pub fn serialize(doc: &SubtitleDocument) -> String {
+    let mut result = String::new();
+    
+    for block in &doc.blocks {
+        match block {
+            Block::Raw(line) => result.push_str(line),
+            Block::Cue(cue) => result.push_str(&cue.source_or(dialogue_line(cue))),
+        }
+        result.push('\n');
+    }
+    
+    doc.layout.apply(result)
+}
@@ -0,0 +1,515 @@
+// Decoding of WAV and FLAC audio tracks for voice activity detection
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// Sample rate audio is reduced to; plenty for detecting speech.
+const TARGET_RATE: u32 = 8000;
+
+/// A mono audio track, downsampled for analysis.
+#[derive(Debug, Clone)]
+pub struct Audio {
+    pub sample_rate: u32,
+    /// Samples in `-1.0..=1.0`.
+    pub samples: Vec<f32>,
+}
+
+impl Audio {
+    pub fn duration_ms(&self) -> i64 {
+        self.samples.len() as i64 * 1000 / self.sample_rate.max(1) as i64
+    }
+}
+
+/// Mixes interleaved frames down to mono and averages groups of frames to
+/// reduce the sample rate, so hour-long tracks stay small in memory.
+struct Downmixer {
+    factor: u32,
+    sum: f32,
+    count: u32,
+    audio: Audio,
+}
+
+impl Downmixer {
+    fn new(sample_rate: u32) -> Self {
+        let factor = (sample_rate / TARGET_RATE).max(1);
+        Downmixer {
+            factor,
+            sum: 0.0,
+            count: 0,
+            audio: Audio { sample_rate: sample_rate / factor, samples: Vec::new() },
+        }
+    }
+    
+    /// Adds one frame (one sample per channel).
+    fn push(&mut self, frame: &[f32]) {
+        self.sum += frame.iter().sum::<f32>() / frame.len() as f32;
+        self.count += 1;
+        if self.count == self.factor {
+            self.audio.samples.push(self.sum / self.factor as f32);
+            self.sum = 0.0;
+            self.count = 0;
+        }
+    }
+    
+    fn finish(self) -> Audio {
+        self.audio
+    }
+}
+
+/// Loads a `.wav` or `.flac` file, detected from its signature.
+pub fn load_audio(path: &Path) -> Result<Audio, String> {
+    let file = File::open(path).map_err(|e| format!("cannot open '{}': {}", path.display(), e))?;
+    let mut reader = BufReader::new(file);
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic).map_err(|e| format!("cannot read '{}': {}", path.display(), e))?;
+    
+    match &magic {
+        b"RIFF" => read_wav(reader),
+        b"fLaC" => decode_flac(reader),
+        _ => Err(format!("'{}' is not a WAV or FLAC file", path.display())),
+    }
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Skips `count` bytes of `reader`.
+fn skip(reader: &mut impl Read, count: u64) -> Result<(), String> {
+    let skipped = std::io::copy(&mut reader.take(count), &mut std::io::sink()).map_err(|e| e.to_string())?;
+    if skipped < count {
+        return Err("unexpected end of file".to_string());
+    }
+    Ok(())
+}
+
+/// Largest `fmt ` chunk read from a WAV file; the fields used fit in 26 bytes.
+const MAX_WAV_FMT: u64 = 64;
+
+/// Reads a RIFF/WAVE stream positioned just after the `RIFF` tag.
+///
+/// Supports integer PCM (8 to 32 bits), 32/64-bit float and WAVE_FORMAT_EXTENSIBLE.
+fn read_wav(mut reader: impl Read) -> Result<Audio, String> {
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header).map_err(|e| e.to_string())?;
+    if &header[4..8] != b"WAVE" {
+        return Err("not a WAVE file".to_string());
+    }
+    
+    let mut format = None;
+    loop {
+        let mut chunk = [0u8; 8];
+        reader.read_exact(&mut chunk).map_err(|_| "WAV file has no data chunk".to_string())?;
+        let size = read_u32(&chunk[4..8]);
+        
+        if &chunk[0..4] == b"fmt " {
+            let padded = size as u64 + (size as u64 & 1);
+            let mut fmt = vec![0u8; padded.min(MAX_WAV_FMT) as usize];
+            reader.read_exact(&mut fmt).map_err(|e| e.to_string())?;
+            skip(&mut reader, padded - fmt.len() as u64)?;
+            if fmt.len() < 16 {
+                return Err("WAV fmt chunk is too short".to_string());
+            }
+            let mut tag = read_u16(&fmt[0..2]);
+            if tag == 0xFFFE && fmt.len() >= 26 {
+                tag = read_u16(&fmt[24..26]);
+            }
+            let channels = read_u16(&fmt[2..4]) as usize;
+            let sample_rate = read_u32(&fmt[4..8]);
+            let bits = read_u16(&fmt[14..16]);
+            format = Some((tag, channels, sample_rate, bits));
+        } else if &chunk[0..4] == b"data" {
+            let (tag, channels, sample_rate, bits) = format.ok_or("WAV data chunk before fmt chunk")?;
+            if channels == 0 || sample_rate == 0 {
+                return Err("WAV file has no channels".to_string());
+            }
+            let decode: fn(&[u8]) -> f32 = match (tag, bits) {
+                (1, 8) => |b| (b[0] as f32 - 128.0) / 128.0,
+                (1, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+                (1, 24) => |b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8388608.0,
+                (1, 32) => |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2147483648.0,
+                (3, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+                (3, 64) => |b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32,
+                _ => return Err(format!("unsupported WAV encoding (format {}, {} bits)", tag, bits)),
+            };
+            
+            let sample_size = bits as usize / 8;
+            let frame_size = sample_size * channels;
+            // Streams piped from ffmpeg often leave the size unset; read to the end then
+            let mut remaining = if size == 0 || size == u32::MAX { u64::MAX } else { size as u64 };
+            let mut downmixer = Downmixer::new(sample_rate);
+            let mut buffer = vec![0u8; frame_size * (65536 / frame_size).max(1)];
+            let mut frame = vec![0f32; channels];
+            let mut pending = 0;
+            
+            while remaining > 0 {
+                let want = (buffer.len() - pending).min(remaining.min(usize::MAX as u64) as usize);
+                let read = reader.read(&mut buffer[pending..pending + want]).map_err(|e| e.to_string())?;
+                if read == 0 {
+                    break;
+                }
+                remaining -= read as u64;
+                pending += read;
+                
+                let whole = pending / frame_size * frame_size;
+                for bytes in buffer[..whole].chunks_exact(frame_size) {
+                    for (channel, sample) in bytes.chunks_exact(sample_size).enumerate() {
+                        frame[channel] = decode(sample);
+                    }
+                    downmixer.push(&frame);
+                }
+                buffer.copy_within(whole..pending, 0);
+                pending -= whole;
+            }
+            return Ok(downmixer.finish());
+        } else {
+            skip(&mut reader, size as u64 + (size as u64 & 1))?;
+        }
+    }
+}
+
+/// MSB-first bit reader over a byte slice.
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader { data, pos: 0, bit: 0 }
+    }
+    
+    fn read(&mut self, bits: u32) -> Result<u64, String> {
+        let mut value = 0u64;
+        let mut left = bits;
+        while left > 0 {
+            let byte = *self.data.get(self.pos).ok_or("unexpected end of FLAC stream")?;
+            let available = 8 - self.bit;
+            let take = available.min(left);
+            let chunk = (byte >> (available - take)) & (0xFF >> (8 - take));
+            value = (value << take) | chunk as u64;
+            left -= take;
+            self.bit += take;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+        Ok(value)
+    }
+    
+    fn read_signed(&mut self, bits: u32) -> Result<i64, String> {
+        if bits == 0 {
+            return Ok(0);
+        }
+        let value = self.read(bits)?;
+        Ok(((value << (64 - bits)) as i64) >> (64 - bits))
+    }
+    
+    /// Counts zero bits up to the next one bit.
+    fn read_unary(&mut self) -> Result<u64, String> {
+        let mut count = 0;
+        loop {
+            let byte = *self.data.get(self.pos).ok_or("unexpected end of FLAC stream")?;
+            let rest = byte << self.bit;
+            if rest == 0 {
+                count += (8 - self.bit) as u64;
+                self.bit = 0;
+                self.pos += 1;
+                continue;
+            }
+            let zeros = rest.leading_zeros();
+            count += zeros as u64;
+            self.bit += zeros + 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+            return Ok(count);
+        }
+    }
+    
+    fn align(&mut self) {
+        if self.bit != 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+}
+
+struct StreamInfo {
+    sample_rate: u32,
+    bits: u32,
+}
+
+/// Largest possible FLAC frame: 65535 samples of 8 channels at 33 bits, plus headers.
+const MAX_FLAC_FRAME: usize = 65535 * 8 * 33 / 8 + 64;
+
+/// Decodes a FLAC stream, given everything after the `fLaC` signature.
+///
+/// Frames are decoded from a sliding window, so only a few megabytes of the
+/// file are held in memory at a time.
+pub fn decode_flac(mut input: impl Read) -> Result<Audio, String> {
+    let mut info = None;
+    loop {
+        let mut header = [0u8; 4];
+        input.read_exact(&mut header).map_err(|_| "truncated FLAC metadata")?;
+        let last = header[0] & 0x80 != 0;
+        let kind = header[0] & 0x7F;
+        let mut length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as u64;
+        if kind == 0 {
+            let mut block = [0u8; 14];
+            input.read_exact(&mut block).map_err(|_| "truncated FLAC STREAMINFO")?;
+            length = length.checked_sub(block.len() as u64).ok_or("truncated FLAC STREAMINFO")?;
+            let mut bits = BitReader::new(&block);
+            bits.read(80)?; // block and frame sizes
+            let sample_rate = bits.read(20)? as u32;
+            bits.read(3)?;
+            let sample_bits = bits.read(5)? as u32 + 1;
+            if sample_rate == 0 {
+                return Err("FLAC file has no sample rate".to_string());
+            }
+            info = Some(StreamInfo { sample_rate, bits: sample_bits });
+        }
+        skip(&mut input, length).map_err(|_| "truncated FLAC metadata")?;
+        if last {
+            break;
+        }
+    }
+    let info = info.ok_or("FLAC file has no STREAMINFO block")?;
+    
+    let mut window = Vec::new();
+    let mut pos = 0;
+    let mut eof = false;
+    let mut downmixer = None;
+    loop {
+        // Keep at least one whole frame ahead of `pos`
+        if !eof && window.len() - pos < MAX_FLAC_FRAME {
+            window.drain(..pos);
+            pos = 0;
+            let want = 2 * MAX_FLAC_FRAME - window.len();
+            let read = (&mut input).take(want as u64).read_to_end(&mut window).map_err(|e| e.to_string())?;
+            eof = read < want;
+        }
+        if pos + 2 > window.len() {
+            break;
+        }
+        if window[pos] != 0xFF || window[pos + 1] & 0xFE != 0xF8 {
+            // Skip junk (e.g. trailing tags) until the next frame sync code
+            pos += 1;
+            continue;
+        }
+        let mut reader = BitReader::new(&window[pos..]);
+        match decode_frame(&mut reader, &info, &mut downmixer) {
+            Ok(()) => pos += reader.pos,
+            // A false sync code or a damaged frame: resume the search after it
+            Err(_) => pos += 1,
+        }
+    }
+    
+    downmixer.map(Downmixer::finish).ok_or_else(|| "FLAC file has no audio frames".to_string())
+}
+
+/// Decodes one frame into the downmixer, creating it on the first frame.
+fn decode_frame(reader: &mut BitReader, info: &StreamInfo, downmixer: &mut Option<Downmixer>) -> Result<(), String> {
+    reader.read(15)?; // sync code and reserved bit
+    reader.read(1)?; // blocking strategy
+    let block_code = reader.read(4)?;
+    let rate_code = reader.read(4)?;
+    let channel_code = reader.read(4)? as usize;
+    let size_code = reader.read(3)?;
+    reader.read(1)?;
+    
+    // UTF-8 style coded frame/sample number
+    let first = reader.read(8)?;
+    let extra = (first as u8).leading_ones().saturating_sub(1);
+    reader.read(8 * extra)?;
+    
+    let block_size = match block_code {
+        1 => 192,
+        2..=5 => 576 << (block_code - 2),
+        6 => reader.read(8)? as usize + 1,
+        7 => reader.read(16)? as usize + 1,
+        8..=15 => 256 << (block_code - 8),
+        _ => return Err("invalid FLAC block size".to_string()),
+    };
+    let sample_rate = match rate_code {
+        0 => info.sample_rate,
+        1 => 88200,
+        2 => 176400,
+        3 => 192000,
+        4 => 8000,
+        5 => 16000,
+        6 => 22050,
+        7 => 24000,
+        8 => 32000,
+        9 => 44100,
+        10 => 48000,
+        11 => 96000,
+        12 => reader.read(8)? as u32 * 1000,
+        13 => reader.read(16)? as u32,
+        14 => reader.read(16)? as u32 * 10,
+        _ => return Err("invalid FLAC sample rate".to_string()),
+    };
+    if sample_rate == 0 {
+        return Err("invalid FLAC sample rate".to_string());
+    }
+    let bits = match size_code {
+        0 => info.bits,
+        1 => 8,
+        2 => 12,
+        4 => 16,
+        5 => 20,
+        6 => 24,
+        7 => 32,
+        _ => return Err("invalid FLAC sample size".to_string()),
+    };
+    reader.read(8)?; // header CRC-8
+    
+    let channels = match channel_code {
+        0..=7 => channel_code + 1,
+        8..=10 => 2,
+        _ => return Err("invalid FLAC channel assignment".to_string()),
+    };
+    
+    let mut decoded = Vec::with_capacity(channels);
+    for channel in 0..channels {
+        // The side channel of a stereo pair carries one extra bit
+        let side = matches!((channel_code, channel), (8, 1) | (9, 0) | (10, 1));
+        decoded.push(decode_subframe(reader, block_size, bits + side as u32)?);
+    }
+    reader.align();
+    reader.read(16)?; // frame CRC-16
+    
+    if let [first, second] = decoded.as_mut_slice() {
+        for (a, b) in first.iter_mut().zip(second.iter_mut()) {
+            match channel_code {
+                // left/side
+                8 => *b = *a - *b,
+                // side/right
+                9 => *a += *b,
+                // mid/side
+                10 => {
+                    let side = *b;
+                    let mid = (*a << 1) | (side & 1);
+                    *a = (mid + side) >> 1;
+                    *b = (mid - side) >> 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    
+    let downmixer = downmixer.get_or_insert_with(|| Downmixer::new(sample_rate));
+    let scale = (1u64 << (bits - 1)) as f32;
+    let mut frame = vec![0f32; channels];
+    for i in 0..block_size {
+        for (channel, samples) in decoded.iter().enumerate() {
+            frame[channel] = samples[i] as f32 / scale;
+        }
+        downmixer.push(&frame);
+    }
+    
+    Ok(())
+}
+
+fn decode_subframe(reader: &mut BitReader, block_size: usize, bits: u32) -> Result<Vec<i64>, String> {
+    reader.read(1)?;
+    let kind = reader.read(6)?;
+    let wasted = if reader.read(1)? == 1 { reader.read_unary()? + 1 } else { 0 };
+    let sample_bits = u32::try_from(wasted).ok().and_then(|wasted| bits.checked_sub(wasted)).ok_or("invalid FLAC wasted bits")?;
+    
+    let mut samples = match kind {
+        0 => vec![reader.read_signed(sample_bits)?; block_size],
+        1 => (0..block_size).map(|_| reader.read_signed(sample_bits)).collect::<Result<_, _>>()?,
+        8..=12 => {
+            let order = (kind - 8) as usize;
+            let mut samples = read_warmup(reader, order, sample_bits)?;
+            read_residual(reader, block_size, order, &mut samples)?;
+            predict(&mut samples, FIXED_COEFFICIENTS[order], 0)?;
+            samples
+        }
+        32..=63 => {
+            let order = (kind - 31) as usize;
+            let mut samples = read_warmup(reader, order, sample_bits)?;
+            let precision = reader.read(4)? as u32 + 1;
+            let shift = reader.read_signed(5)?.max(0);
+            let coefficients: Vec<i64> = (0..order).map(|_| reader.read_signed(precision)).collect::<Result<_, _>>()?;
+            read_residual(reader, block_size, order, &mut samples)?;
+            predict(&mut samples, &coefficients, shift)?;
+            samples
+        }
+        _ => return Err(format!("invalid FLAC subframe type {}", kind)),
+    };
+    
+    // A damaged frame can decode to samples wider than the stream allows
+    let limit = 1i64 << (bits - 1);
+    for sample in &mut samples {
+        *sample = sample.checked_mul(1 << wasted).filter(|s| (-limit..limit).contains(s)).ok_or(OVERFLOW)?;
+    }
+    Ok(samples)
+}
+
+const OVERFLOW: &str = "FLAC sample out of range";
+
+/// Coefficients of the fixed predictors, by order.
+const FIXED_COEFFICIENTS: [&[i64]; 5] = [&[], &[1], &[2, -1], &[3, -3, 1], &[4, -6, 4, -1]];
+
+/// Adds the linear prediction from the previous samples to each residual,
+/// failing instead of overflowing on damaged input.
+fn predict(samples: &mut [i64], coefficients: &[i64], shift: i64) -> Result<(), String> {
+    for i in coefficients.len()..samples.len() {
+        let mut prediction = 0i64;
+        for (j, c) in coefficients.iter().enumerate() {
+            prediction = c.checked_mul(samples[i - 1 - j]).and_then(|p| prediction.checked_add(p)).ok_or(OVERFLOW)?;
+        }
+        samples[i] = samples[i].checked_add(prediction >> shift).ok_or(OVERFLOW)?;
+    }
+    Ok(())
+}
+
+fn read_warmup(reader: &mut BitReader, order: usize, bits: u32) -> Result<Vec<i64>, String> {
+    (0..order).map(|_| reader.read_signed(bits)).collect()
+}
+
+/// Reads Rice-coded residuals, appending them to the warm-up samples.
+fn read_residual(reader: &mut BitReader, block_size: usize, order: usize, samples: &mut Vec<i64>) -> Result<(), String> {
+    let method = reader.read(2)?;
+    let (param_bits, escape) = match method {
+        0 => (4, 15),
+        1 => (5, 31),
+        _ => return Err("invalid FLAC residual coding".to_string()),
+    };
+    let partition_order = reader.read(4)? as u32;
+    let partitions = 1usize << partition_order;
+    
+    for partition in 0..partitions {
+        let mut count = block_size >> partition_order;
+        if partition == 0 {
+            count = count.checked_sub(order).ok_or("invalid FLAC partition size")?;
+        }
+        let param = reader.read(param_bits)? as u32;
+        if param == escape {
+            let raw_bits = reader.read(5)? as u32;
+            for _ in 0..count {
+                samples.push(reader.read_signed(raw_bits)?);
+            }
+        } else {
+            for _ in 0..count {
+                let value = (reader.read_unary()? << param) | reader.read(param)?;
+                samples.push(((value >> 1) as i64) ^ -((value & 1) as i64));
+            }
+        }
+    }
+    
+    if samples.len() != block_size {
+        return Err("FLAC residual does not fill the block".to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,436 @@
+// Command-line argument parsing
+
+use std::path::PathBuf;
+use subsync::timing::{fps_scale, parse_fps};
+use subsync::{
+    parse_timestamp_any, Anchor, AnchorMode, Encoding, Extensions, LanguageStyle, Ratio, SeasonMap, SubtitleFormat,
+};
+
+/// How new subtitle timings are worked out.
+pub enum SyncMode {
+    /// Stretch by `scale`, then shift by a fixed amount.
+    Shift { scale: Ratio, shift_seconds: f64 },
+    /// Map known (old, new) anchor points.
+    Anchors { anchors: Vec<Anchor>, mode: AnchorMode },
+    /// Align against a correctly timed subtitle (a file, or a folder matched by episode).
+    Reference { path: PathBuf, detect_scale: bool, detect_drift: bool, max_offset_ms: i64 },
+    /// Align against speech detected in a WAV/FLAC audio track (a file, or a folder matched by episode).
+    Audio { path: PathBuf, detect_scale: bool, max_offset_ms: i64 },
+}
+
+/// What to do when several subtitles would be written to the same file.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Only the first subtitle (by path) gets the name, and only if no file has it yet.
+    Skip,
+    /// The first free name is kept, the others get a numbered suffix.
+    Suffix,
+    /// Write anyway; the last subtitle wins.
+    Overwrite,
+    /// Stop before changing anything.
+    Fail,
+}
+
+pub struct Options {
+    pub folder_path: PathBuf,
+    pub sync: SyncMode,
+    pub target_format: Option<SubtitleFormat>,
+    pub ass_header: Option<PathBuf>,
+    pub dry_run: bool,
+    /// Show how the episode number (or movie title) of each file was read.
+    pub verbose: bool,
+    /// Also scan subfolders, writing each output next to its video.
+    pub recursive: bool,
+    /// Match files by movie title instead of episode number.
+    pub movies: bool,
+    pub on_conflict: ConflictPolicy,
+    pub language_style: LanguageStyle,
+    /// Join the subtitles of the episodes in a multi-episode video into one file.
+    pub merge_episodes: bool,
+    /// Length of each earlier episode when merging; worked out from the cues if not given.
+    pub episode_duration_ms: Option<i64>,
+    /// Season lengths given with `--season-lengths`.
+    pub season_lengths: Option<SeasonMap>,
+    /// Mapping file given with `--episode-map`.
+    pub episode_map: Option<PathBuf>,
+    /// Extensions taken for videos and subtitles.
+    pub extensions: Extensions,
+    /// Encoding the subtitles are read in; detected for each file if not given.
+    pub input_encoding: Option<Encoding>,
+    /// Encoding the subtitles are written in; each file's own if not given.
+    pub output_encoding: Option<Encoding>,
+}
+
+/// What the program was asked to do.
+pub enum Command {
+    /// Shift, convert and rename the subtitles of a folder.
+    Run(Options),
+    /// Revert a previous run in a folder (the latest one if no run id is given).
+    Undo { folder_path: PathBuf, run_id: Option<String> },
+    /// Print the usage.
+    Help,
+}
+
+pub fn print_usage(program: &str) {
+    eprintln!("Usage: {} [options] <folder_path> <shift_seconds>", program);
+    eprintln!("       {} [options] --anchor <old>=<new> [--anchor <old>=<new> ...] <folder_path>", program);
+    eprintln!("       {} [options] --reference <file_or_folder> <folder_path>", program);
+    eprintln!("       {} [options] --audio <file_or_folder> <folder_path>", program);
+    eprintln!("       {} undo <folder_path> [run-id]", program);
+    eprintln!("Example: {} ./subtitles -5.43", program);
+    eprintln!("\nThis will:");
+    eprintln!("  1. Process all subtitle files (.srt, .ass, .vtt) in the folder");
+    eprintln!("  2. Shift timestamps by the specified amount (negative = earlier)");
+    eprintln!("  3. Rename subtitles to match video files based on episode numbers");
+    eprintln!("  4. Keep a journal and backups in <folder_path>/.subsync so 'undo' can restore the originals");
+    eprintln!("\nOptions:");
+    eprintln!("  --dry-run              Show what would be shifted and renamed without changing any file");
+    eprintln!("  -v, --verbose          Show how the episode number of each file was found");
+    eprintln!("  --recursive            Also scan subfolders (Subs/, season folders); each subtitle is paired with the");
+    eprintln!("                         nearest video of its episode and written next to it");
+    eprintln!("  --movies               Match subtitles to movies by title instead of episode number; a folder");
+    eprintln!("                         with a single video gets all its subtitles");
+    eprintln!("  --scale <factor>       Stretch all timestamps by this factor before shifting (e.g. 1.0427 or 25/23.976)");
+    eprintln!("  --fps-from <fps>       Frame rate the subtitles were timed for (use with --fps-to)");
+    eprintln!("  --fps-to <fps>         Frame rate of the video; stretches timestamps by fps-from/fps-to");
+    eprintln!("  --anchor <old>=<new>   Sync from known points instead of a fixed shift; <old> is a");
+    eprintln!("                         timestamp or #N for the N-th cue (e.g. #12=00:01:04,100)");
+    eprintln!("  --anchor-mode <mode>   Between anchors: 'interpolate' (default) stretches linearly,");
+    eprintln!("                         'step' keeps each anchor's offset until the next one");
+    eprintln!("  --reference <path>     Detect the shift by aligning against a correctly timed subtitle;");
+    eprintln!("                         a folder is searched for the subtitle with the same episode number");
+    eprintln!("  --audio <path>         Detect the shift by finding speech in a decoded WAV/FLAC audio track;");
+    eprintln!("                         a folder is searched for the track with the same episode number");
+    eprintln!("  --detect-scale         With --reference/--audio, also try framerate conversions (25 <-> 23.976 <-> 24)");
+    eprintln!("  --detect-drift         With --reference, also correct slow drift (always on with --audio)");
+    eprintln!("  --max-offset <secs>    With --reference/--audio, largest shift searched for (default 600)");
+    eprintln!("  --on-conflict <policy> When several subtitles map to one file: 'suffix' (default) numbers");
+    eprintln!("                         the extra ones, 'skip' leaves them, 'overwrite' keeps the last, 'fail' stops");
+    eprintln!("  --lang-style <style>   Language tags kept in new names (e.g. .jpn.forced) are written 'keep' (default,");
+    eprintln!("                         as found), 'alpha2' (ja, pt-BR) or 'alpha3' (jpn, por-BR)");
+    eprintln!("  --merge-episodes       Join the subtitles of both episodes of a double-episode video");
+    eprintln!("                         (e.g. S01E01-E02) into one file, the second offset by the first's length");
+    eprintln!("  --episode-duration <t> With --merge-episodes, length of each episode in seconds or HH:MM:SS,mmm");
+    eprintln!("                         (default: where the previous subtitle's last cue ends)");
+    eprintln!("  --season-lengths <n>   Episodes per season, comma-separated (e.g. 25,25,24), so absolute numbers");
+    eprintln!("                         like '- 113' match seasonal ones like S05E13 and the other way round");
+    eprintln!("  --episode-map <file>   Like --season-lengths, from a file with one '<season> <first>-<last>'");
+    eprintln!("                         line per season (e.g. 'S05 101-124')");
+    eprintln!("  --video-ext <ext,..>   Also take files with these extensions for videos (e.g. rmvb,3gp)");
+    eprintln!("  --subtitle-ext <e=f>   Also take files with these extensions for subtitles, read as srt, ass");
+    eprintln!("                         or vtt (e.g. ssa=ass,txt=srt)");
+    eprintln!("  --ignore-ext <ext,..>  Leave files with these extensions alone (e.g. ts)");
+    eprintln!("                         The three can also be set for every run in the SUBSYNC_VIDEO_EXT,");
+    eprintln!("                         SUBSYNC_SUBTITLE_EXT and SUBSYNC_IGNORE_EXT environment variables");
+    eprintln!("  --input-encoding <e>   Read subtitles in this encoding instead of detecting it (e.g. cp1252,");
+    eprintln!("                         latin2, shift_jis, gbk, utf-16)");
+    eprintln!("  --output-encoding <e>  Write subtitles in this encoding (default: the encoding each was read in)");
+    eprintln!("  --normalize-encoding   Write all subtitles as UTF-8");
+    eprintln!("  --to <srt|ass|vtt>     Convert subtitles to this format while shifting");
+    eprintln!("  --ass-header <file>    Script header (Script Info and V4+ Styles) used when converting to ASS");
+    eprintln!("  -h, --help             Show this help");
+    
+    let extensions = configured_extensions().unwrap_or_default();
+    let subtitles: Vec<&str> = extensions.subtitle().map(|(ext, _)| ext).collect();
+    eprintln!("\nRecognized files:");
+    eprintln!("  Videos:    {}", extensions.video().collect::<Vec<_>>().join(", "));
+    eprintln!("  Subtitles: {}", subtitles.join(", "));
+    eprintln!("\nExit status: 0 if all went well, 1 if nothing could be done (every file failed), 2 for");
+    eprintln!("invalid arguments, 3 if some files failed and the others were processed");
+}
+
+/// Environment variables holding the same lists as the extension options, for
+/// settings kept across runs; the options on the command line apply after them.
+const EXTENSION_VARIABLES: [(&str, &str); 3] = [
+    ("SUBSYNC_VIDEO_EXT", "--video-ext"),
+    ("SUBSYNC_SUBTITLE_EXT", "--subtitle-ext"),
+    ("SUBSYNC_IGNORE_EXT", "--ignore-ext"),
+];
+
+/// The default extensions with the ones set in the environment applied.
+fn configured_extensions() -> Result<Extensions, String> {
+    let mut extensions = Extensions::default();
+    for (variable, option) in EXTENSION_VARIABLES {
+        if let Ok(value) = std::env::var(variable) {
+            apply_extension_option(&mut extensions, option, &value).map_err(|message| format!("{}: {}", variable, message))?;
+        }
+    }
+    Ok(extensions)
+}
+
+/// Applies `--video-ext`, `--subtitle-ext` or `--ignore-ext` with its value.
+fn apply_extension_option(extensions: &mut Extensions, option: &str, value: &str) -> Result<(), String> {
+    match option {
+        "--video-ext" => extension_list(value).for_each(|ext| extensions.add_video(ext)),
+        "--subtitle-ext" => {
+            for entry in extension_list(value) {
+                let (ext, format) = entry.split_once('=').unwrap_or((entry, entry));
+                let format = SubtitleFormat::from_extension(format.trim())
+                    .ok_or_else(|| format!("Unknown subtitle format for '{}', expected <ext>=srt|ass|vtt", entry))?;
+                extensions.add_subtitle(ext, format);
+            }
+        }
+        _ => extension_list(value).for_each(|ext| extensions.remove(ext)),
+    }
+    Ok(())
+}
+
+/// Splits a comma-separated list of extensions.
+fn extension_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|ext| !ext.is_empty())
+}
+
+fn option_value<'a>(args: &mut impl Iterator<Item = &'a String>, name: &str) -> Result<&'a String, String> {
+    args.next().ok_or_else(|| format!("Missing value for {}", name))
+}
+
+fn parse_undo(args: &[String]) -> Result<Command, String> {
+    if let Some(arg) = args.iter().find(|arg| arg.starts_with("--")) {
+        return Err(format!("Unknown option '{}' for undo", arg));
+    }
+    match args {
+        [folder_path] => Ok(Command::Undo { folder_path: PathBuf::from(folder_path), run_id: None }),
+        [folder_path, run_id] => Ok(Command::Undo { folder_path: PathBuf::from(folder_path), run_id: Some(run_id.clone()) }),
+        _ => Err("Expected undo <folder_path> [run-id]".to_string()),
+    }
+}
+
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    if args.get(1).is_some_and(|arg| arg == "undo") {
+        return parse_undo(&args[2..]);
+    }
+    if args.iter().skip(1).any(|arg| arg == "--help" || arg == "-h") {
+        return Ok(Command::Help);
+    }
+    
+    let mut positional = Vec::new();
+    let mut target_format = None;
+    let mut ass_header = None;
+    let mut dry_run = false;
+    let mut verbose = false;
+    let mut recursive = false;
+    let mut movies = false;
+    let mut on_conflict = ConflictPolicy::Suffix;
+    let mut language_style = LanguageStyle::Keep;
+    let mut merge_episodes = false;
+    let mut episode_duration_ms = None;
+    let mut season_lengths = None;
+    let mut episode_map = None;
+    let mut extensions = configured_extensions()?;
+    let mut input_encoding = None;
+    let mut output_encoding = None;
+    let mut normalize_encoding = false;
+    let mut scale = None;
+    let mut fps_from = None;
+    let mut fps_to = None;
+    let mut anchors = Vec::new();
+    let mut anchor_mode = None;
+    let mut reference = None;
+    let mut audio = None;
+    let mut detect_scale = false;
+    let mut detect_drift = false;
+    let mut max_offset = None;
+    
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--verbose" | "-v" => verbose = true,
+            "--recursive" => recursive = true,
+            "--movies" => movies = true,
+            "--on-conflict" => {
+                on_conflict = match option_value(&mut iter, arg)?.as_str() {
+                    "skip" => ConflictPolicy::Skip,
+                    "suffix" => ConflictPolicy::Suffix,
+                    "overwrite" => ConflictPolicy::Overwrite,
+                    "fail" => ConflictPolicy::Fail,
+                    other => return Err(format!("Unknown conflict policy '{}'", other)),
+                };
+            }
+            "--lang-style" => {
+                language_style = match option_value(&mut iter, arg)?.as_str() {
+                    "keep" => LanguageStyle::Keep,
+                    "alpha2" => LanguageStyle::Alpha2,
+                    "alpha3" => LanguageStyle::Alpha3,
+                    other => return Err(format!("Unknown language style '{}'", other)),
+                };
+            }
+            "--merge-episodes" => merge_episodes = true,
+            "--episode-duration" => {
+                let value = option_value(&mut iter, arg)?;
+                let duration = match value.parse::<f64>() {
+                    Ok(seconds) => Some((seconds * 1000.0).round() as i64),
+                    Err(_) => parse_timestamp_any(value),
+                };
+                episode_duration_ms = Some(
+                    duration
+                        .filter(|ms| *ms > 0)
+                        .ok_or_else(|| format!("Invalid episode duration '{}'", value))?,
+                );
+            }
+            "--season-lengths" => {
+                let value = option_value(&mut iter, arg)?;
+                let map = SeasonMap::parse_lengths(value).map_err(|message| format!("Invalid season lengths: {}", message))?;
+                season_lengths = Some(map);
+            }
+            "--episode-map" => episode_map = Some(PathBuf::from(option_value(&mut iter, arg)?)),
+            "--video-ext" | "--subtitle-ext" | "--ignore-ext" => {
+                apply_extension_option(&mut extensions, arg, option_value(&mut iter, arg)?)?;
+            }
+            "--input-encoding" | "--output-encoding" => {
+                let value = option_value(&mut iter, arg)?;
+                let encoding = Encoding::from_label(value).ok_or_else(|| format!("Unknown encoding '{}'", value))?;
+                if arg == "--input-encoding" {
+                    input_encoding = Some(encoding);
+                } else {
+                    output_encoding = Some(encoding);
+                }
+            }
+            "--normalize-encoding" => normalize_encoding = true,
+            "--to" => {
+                let value = option_value(&mut iter, arg)?;
+                let format = SubtitleFormat::from_extension(value)
+                    .ok_or_else(|| format!("Unknown subtitle format '{}'", value))?;
+                target_format = Some(format);
+            }
+            "--scale" => {
+                let value = option_value(&mut iter, arg)?;
+                let ratio = Ratio::parse(value)
+                    .filter(|ratio| ratio.num > 0)
+                    .ok_or_else(|| format!("Invalid scale factor '{}'", value))?;
+                scale = Some(ratio);
+            }
+            "--fps-from" | "--fps-to" => {
+                let value = option_value(&mut iter, arg)?;
+                let fps = parse_fps(value).ok_or_else(|| format!("Invalid frame rate '{}'", value))?;
+                if arg == "--fps-from" {
+                    fps_from = Some(fps);
+                } else {
+                    fps_to = Some(fps);
+                }
+            }
+            "--anchor" => {
+                let value = option_value(&mut iter, arg)?;
+                anchors.push(Anchor::parse(value).ok_or_else(|| format!("Invalid anchor '{}'", value))?);
+            }
+            "--anchor-mode" => {
+                anchor_mode = Some(match option_value(&mut iter, arg)?.as_str() {
+                    "step" => AnchorMode::Step,
+                    "interpolate" => AnchorMode::Interpolate,
+                    other => return Err(format!("Unknown anchor mode '{}'", other)),
+                });
+            }
+            "--reference" => reference = Some(PathBuf::from(option_value(&mut iter, arg)?)),
+            "--audio" => audio = Some(PathBuf::from(option_value(&mut iter, arg)?)),
+            "--detect-scale" => detect_scale = true,
+            "--detect-drift" => detect_drift = true,
+            "--max-offset" => {
+                let value = option_value(&mut iter, arg)?;
+                let seconds: f64 = value
+                    .parse()
+                    .ok()
+                    .filter(|seconds: &f64| *seconds > 0.0)
+                    .ok_or_else(|| format!("Invalid maximum offset '{}'", value))?;
+                max_offset = Some((seconds * 1000.0) as i64);
+            }
+            "--ass-header" => ass_header = Some(PathBuf::from(option_value(&mut iter, arg)?)),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'", arg)),
+            _ => positional.push(arg),
+        }
+    }
+    
+    if episode_duration_ms.is_some() && !merge_episodes {
+        return Err("--episode-duration needs --merge-episodes".to_string());
+    }
+    if season_lengths.is_some() && episode_map.is_some() {
+        return Err("--season-lengths and --episode-map cannot be combined".to_string());
+    }
+    if normalize_encoding && output_encoding.is_some() {
+        return Err("--normalize-encoding cannot be combined with --output-encoding".to_string());
+    }
+    if normalize_encoding {
+        output_encoding = Some(Encoding::Utf8);
+    }
+    if movies && (merge_episodes || season_lengths.is_some() || episode_map.is_some()) {
+        return Err("--movies cannot be combined with --merge-episodes, --season-lengths or --episode-map".to_string());
+    }
+    if merge_episodes && !anchors.is_empty() {
+        return Err("--merge-episodes cannot be combined with --anchor".to_string());
+    }
+    if anchor_mode.is_some() && anchors.is_empty() {
+        return Err("--anchor-mode needs at least one --anchor".to_string());
+    }
+    if (detect_scale || max_offset.is_some()) && reference.is_none() && audio.is_none() {
+        return Err("--detect-scale and --max-offset need --reference or --audio".to_string());
+    }
+    if detect_drift && reference.is_none() {
+        return Err("--detect-drift needs --reference".to_string());
+    }
+    if [!anchors.is_empty(), reference.is_some(), audio.is_some()].iter().filter(|&&given| given).count() > 1 {
+        return Err("--anchor, --reference and --audio cannot be combined".to_string());
+    }
+    
+    let scale = match (scale, fps_from, fps_to) {
+        (Some(_), Some(_), _) | (Some(_), _, Some(_)) => {
+            return Err("--scale cannot be combined with --fps-from/--fps-to".to_string());
+        }
+        (Some(scale), None, None) => Some(scale),
+        (None, Some(from), Some(to)) => Some(fps_scale(from, to).ok_or("Frame rate conversion out of range")?),
+        (None, None, None) => None,
+        (None, _, _) => return Err("--fps-from and --fps-to must be given together".to_string()),
+    };
+    
+    let sync = if let Some(path) = reference {
+        if positional.len() != 1 {
+            return Err("Expected only <folder_path> when syncing against a reference".to_string());
+        }
+        if scale.is_some() {
+            return Err("--reference cannot be combined with --scale/--fps-*, use --detect-scale".to_string());
+        }
+        SyncMode::Reference { path, detect_scale, detect_drift, max_offset_ms: max_offset.unwrap_or(600_000) }
+    } else if let Some(path) = audio {
+        if positional.len() != 1 {
+            return Err("Expected only <folder_path> when syncing against audio".to_string());
+        }
+        if scale.is_some() {
+            return Err("--audio cannot be combined with --scale/--fps-*, use --detect-scale".to_string());
+        }
+        SyncMode::Audio { path, detect_scale, max_offset_ms: max_offset.unwrap_or(600_000) }
+    } else if !anchors.is_empty() {
+        if positional.len() != 1 {
+            return Err("Expected only <folder_path> when syncing from anchors".to_string());
+        }
+        if scale.is_some() {
+            return Err("--anchor works out the scale itself and cannot be combined with --scale/--fps-*".to_string());
+        }
+        SyncMode::Anchors { anchors, mode: anchor_mode.unwrap_or(AnchorMode::Interpolate) }
+    } else {
+        if positional.len() != 2 {
+            return Err("Expected <folder_path> and <shift_seconds>".to_string());
+        }
+        let shift_seconds = positional[1]
+            .parse()
+            .map_err(|_| format!("Invalid shift value '{}'", positional[1]))?;
+        SyncMode::Shift { scale: scale.unwrap_or(Ratio::ONE), shift_seconds }
+    };
+    
+    Ok(Command::Run(Options {
+        folder_path: PathBuf::from(positional[0]),
+        sync,
+        target_format,
+        ass_header,
+        dry_run,
+        verbose,
+        recursive,
+        movies,
+        on_conflict,
+        language_style,
+        merge_episodes,
+        episode_duration_ms,
+        season_lengths,
+        episode_map,
+        extensions,
+        input_encoding,
+        output_encoding,
+    }))
+}
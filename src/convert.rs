@@ -0,0 +1,224 @@
+// Conversion between subtitle formats, including inline markup
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::subtitle::{AssEvent, Block, Cue, CueMeta, SrtCoordinates, SubtitleDocument, SubtitleFormat};
+
+/// Script header used when converting to ASS without a custom one.
+pub const DEFAULT_ASS_HEADER: &str = "\
+[Script Info]
+ScriptType: v4.00+
+WrapStyle: 0
+ScaledBorderAndShadow: yes
+PlayResX: 1920
+PlayResY: 1080
+
+[V4+ Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding
+Style: Default,Arial,72,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,3,1,2,60,60,50,1
+";
+
+const ASS_EVENTS_HEADER: &str = "\
+[Events]
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text";
+
+/// Options controlling format conversion.
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    /// Script header (everything before `[Events]`) written when converting to ASS.
+    pub ass_header: String,
+    /// Style name given to converted ASS events.
+    pub ass_style: String,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        ConvertOptions {
+            ass_header: DEFAULT_ASS_HEADER.to_string(),
+            ass_style: "Default".to_string(),
+        }
+    }
+}
+
+static ASS_OVERRIDE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{([^}]*)\}").unwrap());
+/// An override tag without its backslash: `i1`, `b0`, `p1`, or the primary
+/// colour `c&HBBGGRR&` (`c` alone resets it).
+static ASS_TAG_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:(i|b|u|s|p)(\d+)|1?c(?:&H[0-9A-Fa-f]{0,2}([0-9A-Fa-f]{6})&?)?)$").unwrap());
+static HTML_TAG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<(/?)([A-Za-z]+)([^>]*)>").unwrap());
+static FONT_COLOR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)color\s*=\s*"?#([0-9a-f]{2})([0-9a-f]{2})([0-9a-f]{2})"?"#).unwrap());
+
+/// Converts ASS override tags to SRT-style `<i>`/`<b>`/`<u>`/`<s>` and
+/// `<font color>` markup.
+///
+/// Other override tags are dropped, as is text drawn in vector drawing mode (`\p1`).
+pub fn ass_to_html(text: &str) -> String {
+    let mut result = String::new();
+    let mut drawing = false;
+    let mut last = 0;
+    
+    for caps in ASS_OVERRIDE_RE.captures_iter(text) {
+        let block = caps.get(0).unwrap();
+        if !drawing {
+            result.push_str(&text[last..block.start()]);
+        }
+        last = block.end();
+        
+        for tag in caps[1].split('\\').filter_map(|tag| ASS_TAG_RE.captures(tag.trim())) {
+            let Some(name) = tag.get(1) else {
+                // ASS colours are written blue-green-red
+                match tag.get(3).map(|bgr| bgr.as_str().to_lowercase()) {
+                    Some(bgr) => result.push_str(&format!("<font color=\"#{}{}{}\">", &bgr[4..6], &bgr[2..4], &bgr[0..2])),
+                    None => result.push_str("</font>"),
+                }
+                continue;
+            };
+            let enabled = &tag[2] != "0";
+            match name.as_str() {
+                "p" => drawing = enabled,
+                name if enabled => result.push_str(&format!("<{}>", name)),
+                name => result.push_str(&format!("</{}>", name)),
+            }
+        }
+    }
+    if !drawing {
+        result.push_str(&text[last..]);
+    }
+    
+    result.replace("\\h", "\u{a0}").replace("\\n", " ")
+}
+
+/// Converts SRT/WebVTT markup to ASS override tags.
+///
+/// `<i>`, `<b>`, `<u>`, `<s>` and `<font color>` are mapped; other tags are dropped.
+pub fn html_to_ass(text: &str) -> String {
+    HTML_TAG_RE
+        .replace_all(text, |caps: &regex::Captures| {
+            let closing = &caps[1] == "/";
+            let name = caps[2].to_lowercase();
+            match name.as_str() {
+                "i" | "b" | "u" | "s" => format!("{{\\{}{}}}", name, if closing { 0 } else { 1 }),
+                "font" if closing => "{\\c}".to_string(),
+                "font" => match FONT_COLOR_RE.captures(&caps[3]) {
+                    // ASS colours are written blue-green-red
+                    Some(color) => {
+                        let bgr = format!("{}{}{}", &color[3], &color[2], &color[1]);
+                        format!("{{\\c&H{}&}}", bgr.to_uppercase())
+                    }
+                    None => String::new(),
+                },
+                _ => String::new(),
+            }
+        })
+        .into_owned()
+}
+
+/// Removes every markup tag whose name is not in `allowed`.
+fn filter_tags(text: &str, allowed: &[&str]) -> String {
+    HTML_TAG_RE
+        .replace_all(text, |caps: &regex::Captures| {
+            if allowed.contains(&caps[2].to_lowercase().as_str()) {
+                caps[0].to_string()
+            } else {
+                String::new()
+            }
+        })
+        .into_owned()
+}
+
+/// The ASS override placing text in the middle of an SRT cue's coordinate box,
+/// taking the box's pixels for the script's (`PlayResX`/`PlayResY`).
+pub fn coordinates_to_ass(coordinates: &SrtCoordinates) -> String {
+    let x = (coordinates.x1 + coordinates.x2) / 2;
+    let y = (coordinates.y1 + coordinates.y2) / 2;
+    format!("{{\\an5\\pos({},{})}}", x, y)
+}
+
+/// Converts the inline markup of a cue's text from one format to another.
+pub fn convert_text(text: &str, from: SubtitleFormat, to: SubtitleFormat) -> String {
+    match (from, to) {
+        (from, to) if from == to => text.to_string(),
+        (SubtitleFormat::Ass, SubtitleFormat::Srt) => ass_to_html(text),
+        (SubtitleFormat::Ass, SubtitleFormat::Vtt) => filter_tags(&ass_to_html(text), &["i", "b", "u"]),
+        (_, SubtitleFormat::Ass) => html_to_ass(text),
+        (SubtitleFormat::Vtt, SubtitleFormat::Srt) => filter_tags(text, &["i", "b", "u"]),
+        (_, _) => filter_tags(text, &["i", "b", "u", "c", "v", "lang", "ruby", "rt"]),
+    }
+}
+
+fn target_meta(cue: &Cue, index: usize, to: SubtitleFormat, options: &ConvertOptions) -> CueMeta {
+    match to {
+        SubtitleFormat::Srt => CueMeta::Srt { index: Some(index.to_string()), coordinates: None },
+        SubtitleFormat::Ass => CueMeta::Ass(AssEvent {
+            layer: "0".to_string(),
+            style: options.ass_style.clone(),
+            name: String::new(),
+            margin_l: "0".to_string(),
+            margin_r: "0".to_string(),
+            margin_v: "0".to_string(),
+            effect: String::new(),
+        }),
+        SubtitleFormat::Vtt => match &cue.meta {
+            CueMeta::Vtt { identifier, settings } => CueMeta::Vtt { identifier: identifier.clone(), settings: settings.clone() },
+            _ => CueMeta::Vtt { identifier: None, settings: String::new() },
+        },
+    }
+}
+
+impl SubtitleDocument {
+    /// Converts the document to another format.
+    ///
+    /// Cue timings and text are carried over with their markup translated.
+    /// Format-specific non-cue content (ASS styles, WebVTT NOTE blocks, ...)
+    /// is replaced by the target format's own header.
+    pub fn convert(&self, to: SubtitleFormat, options: &ConvertOptions) -> SubtitleDocument {
+        if self.format == to {
+            return self.clone();
+        }
+        
+        let mut blocks = Vec::new();
+        match to {
+            SubtitleFormat::Srt => {}
+            SubtitleFormat::Ass => {
+                let header = options.ass_header.trim_end();
+                blocks.extend(header.lines().map(|line| Block::Raw(line.to_string())));
+                if !header.contains("[Events]") {
+                    blocks.push(Block::Raw(String::new()));
+                    blocks.extend(ASS_EVENTS_HEADER.lines().map(|line| Block::Raw(line.to_string())));
+                }
+            }
+            SubtitleFormat::Vtt => {
+                blocks.push(Block::Raw("WEBVTT".to_string()));
+                blocks.push(Block::Raw(String::new()));
+            }
+        }
+        
+        let mut written = 0;
+        for cue in self.cues() {
+            let mut text = convert_text(&cue.text, self.format, to);
+            if let (SubtitleFormat::Ass, CueMeta::Srt { coordinates: Some(coordinates), .. }) = (to, &cue.meta) {
+                text.insert_str(0, &coordinates_to_ass(coordinates));
+            }
+            // ASS events that were pure drawings have no text left to show
+            if text.trim().is_empty() && !cue.text.trim().is_empty() {
+                continue;
+            }
+            if to != SubtitleFormat::Ass && written > 0 {
+                blocks.push(Block::Raw(String::new()));
+            }
+            written += 1;
+            blocks.push(Block::Cue(Cue {
+                start: cue.start,
+                end: cue.end,
+                meta: target_meta(cue, written, to, options),
+                text,
+                source_line: None,
+            }));
+        }
+        
+        SubtitleDocument { format: to, blocks, layout: self.layout }
+    }
+}
@@ -0,0 +1,441 @@
+// Character encodings of subtitle files: detection, decoding and encoding
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::LazyLock;
+
+use crate::encoding_tables;
+
+/// A character encoding a subtitle file can be read and written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    ShiftJis,
+    Gbk,
+    Windows1250,
+    Windows1251,
+    Windows1252,
+    Windows1253,
+    Windows1254,
+    Iso8859_1,
+    Iso8859_2,
+    Iso8859_5,
+    Iso8859_7,
+    Iso8859_9,
+    Iso8859_15,
+    Koi8R,
+}
+
+/// Encodings tried by [`detect`] when a file is neither Unicode nor pure ASCII,
+/// in order of preference when they read it equally well. The ISO-8859 ones and
+/// windows-1254 read most files like one of these, so they are only used when asked for.
+const LEGACY_CANDIDATES: &[Encoding] = &[
+    Encoding::Windows1252,
+    Encoding::Windows1250,
+    Encoding::Windows1251,
+    Encoding::Koi8R,
+    Encoding::Windows1253,
+    Encoding::ShiftJis,
+    Encoding::Gbk,
+];
+
+/// Trail bytes per lead byte in the double-byte tables.
+const SHIFT_JIS_TRAILS: u8 = 0xFC - 0x40 + 1;
+const GBK_TRAILS: u8 = 0xFE - 0x40 + 1;
+
+/// Every character of a double-byte table, indexed by (lead, trail) position.
+fn table_chars(table: &str) -> Vec<char> {
+    table.chars().collect()
+}
+
+static SHIFT_JIS_CHARS: LazyLock<Vec<char>> = LazyLock::new(|| table_chars(encoding_tables::SHIFT_JIS));
+static GBK_CHARS: LazyLock<Vec<char>> = LazyLock::new(|| table_chars(encoding_tables::GBK));
+
+/// Byte pairs of every character of a double-byte table; where the table has
+/// a character twice (NEC and IBM extensions) the first pair is used.
+fn reverse_table(chars: &[char], lead_of: fn(usize) -> u8, trails: u8) -> HashMap<char, [u8; 2]> {
+    let mut map = HashMap::new();
+    for (index, &c) in chars.iter().enumerate() {
+        if c != char::REPLACEMENT_CHARACTER {
+            let lead = lead_of(index / trails as usize);
+            let trail = 0x40 + (index % trails as usize) as u8;
+            map.entry(c).or_insert([lead, trail]);
+        }
+    }
+    map
+}
+
+fn shift_jis_lead(row: usize) -> u8 {
+    if row < 0x1F { 0x81 + row as u8 } else { 0xE0 + (row - 0x1F) as u8 }
+}
+
+static SHIFT_JIS_BYTES: LazyLock<HashMap<char, [u8; 2]>> =
+    LazyLock::new(|| reverse_table(&SHIFT_JIS_CHARS, shift_jis_lead, SHIFT_JIS_TRAILS));
+static GBK_BYTES: LazyLock<HashMap<char, [u8; 2]>> =
+    LazyLock::new(|| reverse_table(&GBK_CHARS, |row| 0x81 + row as u8, GBK_TRAILS));
+
+impl Encoding {
+    /// Looks up an encoding by name, ignoring case, `-` and `_`: `utf-8`,
+    /// `cp1252`, `latin1`, `sjis`, `gb2312`, ... Plain `utf-16` is little-endian.
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label: String = label.chars().filter(|c| !matches!(c, '-' | '_' | ' ')).flat_map(char::to_lowercase).collect();
+        let encoding = match label.as_str() {
+            "utf8" => Encoding::Utf8,
+            "utf16" | "utf16le" => Encoding::Utf16Le,
+            "utf16be" => Encoding::Utf16Be,
+            "shiftjis" | "sjis" | "cp932" | "windows31j" => Encoding::ShiftJis,
+            "gbk" | "gb2312" | "cp936" | "windows936" => Encoding::Gbk,
+            "windows1250" | "cp1250" => Encoding::Windows1250,
+            "windows1251" | "cp1251" => Encoding::Windows1251,
+            "windows1252" | "cp1252" => Encoding::Windows1252,
+            "windows1253" | "cp1253" => Encoding::Windows1253,
+            "windows1254" | "cp1254" => Encoding::Windows1254,
+            "iso88591" | "latin1" => Encoding::Iso8859_1,
+            "iso88592" | "latin2" => Encoding::Iso8859_2,
+            "iso88595" => Encoding::Iso8859_5,
+            "iso88597" => Encoding::Iso8859_7,
+            "iso88599" | "latin5" => Encoding::Iso8859_9,
+            "iso885915" | "latin9" => Encoding::Iso8859_15,
+            "koi8r" => Encoding::Koi8R,
+            _ => return None,
+        };
+        Some(encoding)
+    }
+    
+    /// The encoding's usual name, e.g. `windows-1252`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::ShiftJis => "Shift_JIS",
+            Encoding::Gbk => "GBK",
+            Encoding::Windows1250 => "windows-1250",
+            Encoding::Windows1251 => "windows-1251",
+            Encoding::Windows1252 => "windows-1252",
+            Encoding::Windows1253 => "windows-1253",
+            Encoding::Windows1254 => "windows-1254",
+            Encoding::Iso8859_1 => "ISO-8859-1",
+            Encoding::Iso8859_2 => "ISO-8859-2",
+            Encoding::Iso8859_5 => "ISO-8859-5",
+            Encoding::Iso8859_7 => "ISO-8859-7",
+            Encoding::Iso8859_9 => "ISO-8859-9",
+            Encoding::Iso8859_15 => "ISO-8859-15",
+            Encoding::Koi8R => "KOI8-R",
+        }
+    }
+    
+    /// The byte order mark of a Unicode encoding.
+    pub fn bom(self) -> Option<&'static [u8]> {
+        match self {
+            Encoding::Utf8 => Some(b"\xEF\xBB\xBF"),
+            Encoding::Utf16Le => Some(b"\xFF\xFE"),
+            Encoding::Utf16Be => Some(b"\xFE\xFF"),
+            _ => None,
+        }
+    }
+    
+    /// Characters of bytes 0x80 to 0xFF of a single-byte encoding.
+    fn high_half(self) -> Option<&'static str> {
+        let table = match self {
+            Encoding::Windows1250 => encoding_tables::WINDOWS_1250,
+            Encoding::Windows1251 => encoding_tables::WINDOWS_1251,
+            Encoding::Windows1252 => encoding_tables::WINDOWS_1252,
+            Encoding::Windows1253 => encoding_tables::WINDOWS_1253,
+            Encoding::Windows1254 => encoding_tables::WINDOWS_1254,
+            Encoding::Iso8859_2 => encoding_tables::ISO_8859_2,
+            Encoding::Iso8859_5 => encoding_tables::ISO_8859_5,
+            Encoding::Iso8859_7 => encoding_tables::ISO_8859_7,
+            Encoding::Iso8859_9 => encoding_tables::ISO_8859_9,
+            Encoding::Iso8859_15 => encoding_tables::ISO_8859_15,
+            Encoding::Koi8R => encoding_tables::KOI8_R,
+            _ => return None,
+        };
+        Some(table)
+    }
+    
+    /// Decodes `bytes` (without a byte order mark); `None` if they are not
+    /// valid in this encoding. Single-byte encodings read any bytes.
+    pub fn decode(self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Encoding::Utf8 => String::from_utf8(bytes.to_vec()).ok(),
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                if !bytes.len().is_multiple_of(2) {
+                    return None;
+                }
+                let units = bytes.chunks(2).map(|pair| match self {
+                    Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                    _ => u16::from_be_bytes([pair[0], pair[1]]),
+                });
+                char::decode_utf16(units).collect::<Result<String, _>>().ok()
+            }
+            Encoding::ShiftJis => decode_shift_jis(bytes),
+            Encoding::Gbk => decode_gbk(bytes),
+            Encoding::Iso8859_1 => Some(bytes.iter().map(|&byte| byte as char).collect()),
+            _ => {
+                let high: Vec<char> = self.high_half()?.chars().collect();
+                Some(bytes.iter().map(|&byte| if byte < 0x80 { byte as char } else { high[byte as usize - 0x80] }).collect())
+            }
+        }
+    }
+    
+    /// Encodes `text` (without a byte order mark); `Err` with the first
+    /// character this encoding cannot represent.
+    pub fn encode(self, text: &str) -> Result<Vec<u8>, char> {
+        match self {
+            Encoding::Utf8 => return Ok(text.as_bytes().to_vec()),
+            Encoding::Utf16Le => return Ok(text.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+            Encoding::Utf16Be => return Ok(text.encode_utf16().flat_map(u16::to_be_bytes).collect()),
+            _ => {}
+        }
+        let high: Vec<char> = self.high_half().map(|table| table.chars().collect()).unwrap_or_default();
+        let mut bytes = Vec::with_capacity(text.len());
+        for c in text.chars() {
+            if c.is_ascii() {
+                bytes.push(c as u8);
+                continue;
+            }
+            match self {
+                Encoding::ShiftJis => match c {
+                    '\u{FF61}'..='\u{FF9F}' => bytes.push((c as u32 - 0xFF61 + 0xA1) as u8),
+                    _ => bytes.extend(SHIFT_JIS_BYTES.get(&c).ok_or(c)?),
+                },
+                Encoding::Gbk => bytes.extend(GBK_BYTES.get(&c).ok_or(c)?),
+                Encoding::Iso8859_1 => bytes.push(u8::try_from(c as u32).map_err(|_| c)?),
+                _ => {
+                    let position = high.iter().position(|&other| other == c).ok_or(c)?;
+                    bytes.push(0x80 + position as u8);
+                }
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+fn decode_shift_jis(bytes: &[u8]) -> Option<String> {
+    let mut text = String::with_capacity(bytes.len());
+    let mut iter = bytes.iter().copied();
+    while let Some(byte) = iter.next() {
+        let row = match byte {
+            0x00..=0x7F => {
+                text.push(byte as char);
+                continue;
+            }
+            // Half-width katakana
+            0xA1..=0xDF => {
+                text.push(char::from_u32(0xFF61 + (byte - 0xA1) as u32)?);
+                continue;
+            }
+            0x81..=0x9F => byte - 0x81,
+            0xE0..=0xFC => byte - 0xE0 + 0x1F,
+            _ => return None,
+        };
+        let trail = iter.next().filter(|trail| (0x40..=0xFC).contains(trail))?;
+        let c = SHIFT_JIS_CHARS[row as usize * SHIFT_JIS_TRAILS as usize + (trail - 0x40) as usize];
+        if c == char::REPLACEMENT_CHARACTER {
+            return None;
+        }
+        text.push(c);
+    }
+    Some(text)
+}
+
+fn decode_gbk(bytes: &[u8]) -> Option<String> {
+    let mut text = String::with_capacity(bytes.len());
+    let mut iter = bytes.iter().copied();
+    while let Some(byte) = iter.next() {
+        if byte < 0x80 {
+            text.push(byte as char);
+            continue;
+        }
+        if !(0x81..=0xFE).contains(&byte) {
+            return None;
+        }
+        let trail = iter.next().filter(|trail| (0x40..=0xFE).contains(trail))?;
+        let c = GBK_CHARS[(byte - 0x81) as usize * GBK_TRAILS as usize + (trail - 0x40) as usize];
+        if c == char::REPLACEMENT_CHARACTER {
+            return None;
+        }
+        text.push(c);
+    }
+    Some(text)
+}
+
+/// The text of a file, with the encoding it was stored in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedText {
+    pub text: String,
+    pub encoding: Encoding,
+    /// The file started with a byte order mark.
+    pub bom: bool,
+}
+
+impl DecodedText {
+    /// Decodes a file's contents: in `encoding` if given (a byte order mark
+    /// for it is skipped), else in the encoding [`detect`] finds. `Err` with
+    /// the encoding if the contents are not valid in it.
+    pub fn decode(bytes: &[u8], encoding: Option<Encoding>) -> Result<DecodedText, Encoding> {
+        let encoding = encoding.unwrap_or_else(|| detect(bytes));
+        let (content, bom) = match encoding.bom() {
+            Some(bom) if bytes.starts_with(bom) => (&bytes[bom.len()..], true),
+            _ => (bytes, false),
+        };
+        let text = encoding.decode(content).ok_or(encoding)?;
+        Ok(DecodedText { text, encoding, bom })
+    }
+}
+
+/// Encodes `text` for writing in `encoding`, after a byte order mark if `bom`
+/// is set and the encoding has one; `Err` with the first character it cannot represent.
+pub fn encode_text(text: &str, encoding: Encoding, bom: bool) -> Result<Vec<u8>, char> {
+    let mut bytes = match encoding.bom() {
+        Some(mark) if bom => mark.to_vec(),
+        _ => Vec::new(),
+    };
+    bytes.extend(encoding.encode(text)?);
+    Ok(bytes)
+}
+
+/// The encoding of a UTF-16 file without a byte order mark, told by where its
+/// ASCII characters put their zero bytes.
+fn detect_utf16(bytes: &[u8]) -> Option<Encoding> {
+    let sample = &bytes[..bytes.len().min(4096) & !1];
+    if sample.is_empty() {
+        return None;
+    }
+    let pairs = sample.len() / 2;
+    let even_zeros = sample.iter().step_by(2).filter(|&&byte| byte == 0).count();
+    let odd_zeros = sample.iter().skip(1).step_by(2).filter(|&&byte| byte == 0).count();
+    if odd_zeros * 2 > pairs && even_zeros * 10 < pairs {
+        Some(Encoding::Utf16Le)
+    } else if even_zeros * 2 > pairs && odd_zeros * 10 < pairs {
+        Some(Encoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// Finds the encoding a file's contents are most likely in: from its byte
+/// order mark, else UTF-16 or UTF-8 if the contents fit, else the legacy
+/// encoding whose reading of them looks most like text. This is a guess for
+/// short or mixed files; `--input-encoding` overrides it.
+pub fn detect(bytes: &[u8]) -> Encoding {
+    for encoding in [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be] {
+        if encoding.bom().is_some_and(|bom| bytes.starts_with(bom)) {
+            return encoding;
+        }
+    }
+    if let Some(encoding) = detect_utf16(bytes)
+        && encoding.decode(&bytes[..bytes.len() & !1]).is_some()
+    {
+        return encoding;
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return Encoding::Utf8;
+    }
+    
+    let mut best = (i64::MIN, Encoding::Windows1252);
+    for &encoding in LEGACY_CANDIDATES {
+        if let Some(text) = encoding.decode(bytes) {
+            let score = text_score(&text);
+            if score > best.0 {
+                best = (score, encoding);
+            }
+        }
+    }
+    best.1
+}
+
+/// Writing systems told apart by [`text_score`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    /// Kana, CJK ideographs and full-width forms, mixed freely in East Asian text.
+    EastAsian,
+    Other,
+}
+
+fn script(c: char) -> Script {
+    match c {
+        'a'..='z' | 'A'..='Z' | '\u{C0}'..='\u{24F}' | '\u{1E00}'..='\u{1EFF}' => Script::Latin,
+        '\u{370}'..='\u{3FF}' => Script::Greek,
+        '\u{400}'..='\u{4FF}' => Script::Cyrillic,
+        '\u{3000}'..='\u{30FF}' | '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{FF00}'..='\u{FFEF}' => {
+            Script::EastAsian
+        }
+        _ => Script::Other,
+    }
+}
+
+/// Hanzi of GB2312, the ones everyday Chinese text is written with. Text in
+/// other encodings read as GBK turns into the rarer ones of its extensions.
+fn is_common_hanzi(c: char) -> bool {
+    ('\u{4E00}'..='\u{9FFF}').contains(&c) && GBK_BYTES.get(&c).is_some_and(|&[lead, trail]| lead >= 0xB0 && trail >= 0xA1)
+}
+
+/// How much a decoding looks like real text: non-ASCII letters count for it,
+/// kana and common hanzi double; control characters, words mixing scripts,
+/// symbols inside words, capitals after small letters and long runs of
+/// accented letters count against it, as they are what reading a file in the
+/// wrong encoding produces.
+fn text_score(text: &str) -> i64 {
+    let mut score = 0;
+    let mut previous = ' ';
+    let mut word_script = None;
+    let mut accented_run = 0;
+    let chars: Vec<char> = text.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii() {
+            if c.is_ascii_control() && !matches!(c, '\t' | '\n' | '\r') {
+                score -= 5;
+            }
+        } else if c.is_control() || c == char::REPLACEMENT_CHARACTER || ('\u{E000}'..='\u{F8FF}').contains(&c) {
+            score -= 5;
+        } else if ('\u{FF61}'..='\u{FF9F}').contains(&c) {
+            // Half-width katakana: rare in subtitles, but what Latin-1 letters become in Shift_JIS
+            score -= 1;
+        } else if ('\u{3040}'..='\u{30FF}').contains(&c) || is_common_hanzi(c) {
+            // Read from two bytes, so worth two single-byte letters
+            score += 2;
+        } else if c.is_alphabetic() {
+            score += 1;
+        } else if !c.is_whitespace()
+            && script(c) != Script::EastAsian
+            && previous.is_alphabetic()
+            && chars.get(i + 1).is_some_and(|next| next.is_alphabetic())
+        {
+            // East Asian punctuation is left out: without spaces it always sits between letters
+            score -= 3;
+        }
+        
+        if c.is_alphabetic() {
+            let current = script(c);
+            if word_script.is_some_and(|word| word != current) {
+                score -= 3;
+            }
+            word_script = Some(current);
+            if previous.is_lowercase() && c.is_uppercase() && !(c.is_ascii() && previous.is_ascii()) {
+                score -= 2;
+            }
+            accented_run = if current == Script::Latin && !c.is_ascii() { accented_run + 1 } else { 0 };
+            if accented_run >= 3 {
+                score -= 2;
+            }
+        } else {
+            word_script = None;
+            accented_run = 0;
+        }
+        previous = c;
+    }
+    score
+}
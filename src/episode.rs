@@ -0,0 +1,153 @@
+// Episode number detection and subtitle/video matching
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::release::ReleaseInfo;
+use crate::seasons::SeasonMap;
+
+/// Longest episode range accepted in a name like `S01E01-E03`, to tell
+/// multi-episode files from trailing numbers such as `-1080p`.
+const MAX_RANGE_EPISODES: u32 = 10;
+
+/// Identity of an episode, or of a run of episodes in one multi-episode file.
+/// `season` is `None` when the name only carries an episode number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EpisodeKey {
+    pub season: Option<u32>,
+    pub episode: u32,
+    /// Last episode of a multi-episode file; equal to `episode` otherwise.
+    pub last_episode: u32,
+}
+
+impl EpisodeKey {
+    pub fn new(season: Option<u32>, episode: u32) -> Self {
+        EpisodeKey { season, episode, last_episode: episode }
+    }
+    
+    /// A key for episodes `first` to `last`, or a single episode if `last` isn't a plausible range end.
+    pub fn range(season: Option<u32>, first: u32, last: Option<u32>) -> Self {
+        match last {
+            Some(last) if last > first && last - first < MAX_RANGE_EPISODES => EpisodeKey { season, episode: first, last_episode: last },
+            _ => EpisodeKey::new(season, first),
+        }
+    }
+    
+    /// Whether this key covers more than one episode.
+    pub fn is_range(&self) -> bool {
+        self.last_episode > self.episode
+    }
+    
+    /// Shares an episode number, and the same season unless one side has no season.
+    pub fn matches(&self, other: &EpisodeKey) -> bool {
+        self.episode <= other.last_episode
+            && other.episode <= self.last_episode
+            && (self.season.is_none() || other.season.is_none() || self.season == other.season)
+    }
+}
+
+impl fmt::Display for EpisodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.season {
+            Some(season) => write!(f, "S{:02}E{:02}", season, self.episode)?,
+            None => write!(f, "{}", self.episode)?,
+        }
+        match (self.is_range(), self.season) {
+            (true, Some(_)) => write!(f, "-E{:02}", self.last_episode),
+            (true, None) => write!(f, "-{}", self.last_episode),
+            (false, _) => Ok(()),
+        }
+    }
+}
+
+/// Folder names that hold one season: `Season 2`, `Series 02`, `Saison 2`, `Staffel 2`, `S02`.
+static SEASON_FOLDER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^(?:(?:season|series|saison|staffel)[\s._-]*|s)(\d{1,2})$").unwrap());
+
+/// Extracts the season and episode (or episode range) from a file name, see [`ReleaseInfo::parse`].
+pub fn extract_episode(filename: &str) -> Option<EpisodeKey> {
+    ReleaseInfo::parse(filename).episode_key()
+}
+
+/// Extracts an episode number from a file name, ignoring the season.
+pub fn extract_episode_number(filename: &str) -> Option<u32> {
+    extract_episode(filename).map(|key| key.episode)
+}
+
+/// Season number of a `Season N`-style folder name.
+pub fn season_from_folder(name: &str) -> Option<u32> {
+    SEASON_FOLDER_RE.captures(name.trim())?[1].parse().ok()
+}
+
+/// Episode key of a file, taking the season from its parent folder when the name has none.
+pub fn episode_key(path: &Path) -> Option<EpisodeKey> {
+    mapped_episode_key(path, None)
+}
+
+/// Like [`episode_key`], but with a season map a number without a season is
+/// read as an absolute one and given its season. Inside a season folder that
+/// only happens when the number falls in that season's absolute range, so both
+/// `Season 5/- 113` and `Season 5/- 13` come out as `S05E13`.
+pub fn mapped_episode_key(path: &Path, seasons: Option<&SeasonMap>) -> Option<EpisodeKey> {
+    let key = extract_episode(path.file_name()?.to_str()?)?;
+    if key.season.is_some() {
+        return Some(key);
+    }
+    let folder_season = path
+        .parent()
+        .and_then(|parent| parent.file_name())
+        .and_then(|name| name.to_str())
+        .and_then(season_from_folder);
+    if let Some(mapped) = seasons.and_then(|seasons| seasons.translate(key))
+        && folder_season.is_none_or(|season| mapped.season == Some(season))
+    {
+        return Some(mapped);
+    }
+    Some(EpisodeKey { season: folder_season, ..key })
+}
+
+/// Returns the video for episode `key`: an exact match, or otherwise the only
+/// video that covers one of its episodes (a multi-episode file, or one side
+/// without a season).
+pub fn find_matching_video(video_files: &[(PathBuf, EpisodeKey)], key: EpisodeKey) -> Option<&PathBuf> {
+    if let Some((path, _)) = video_files.iter().find(|(_, video)| *video == key) {
+        return Some(path);
+    }
+    let mut candidates = video_files.iter().filter(|(_, video)| video.matches(&key));
+    match (candidates.next(), candidates.next()) {
+        (Some((path, _)), None) => Some(path),
+        _ => None,
+    }
+}
+
+/// Number of folder steps from `a` up to the nearest common ancestor and down to `b`.
+pub(crate) fn folder_distance(a: &Path, b: &Path) -> usize {
+    let common = a.components().zip(b.components()).take_while(|(x, y)| x == y).count();
+    a.components().count() + b.components().count() - 2 * common
+}
+
+/// Like [`find_matching_video`], for videos spread over several folders: of
+/// the videos for episode `key`, the one whose folder is nearest to `folder`
+/// wins. Exact matches still come first; among the others the nearest one
+/// must be the only one at its distance.
+pub fn find_nearest_video<'a>(video_files: &'a [(PathBuf, EpisodeKey)], key: EpisodeKey, folder: &Path) -> Option<&'a PathBuf> {
+    let distance = |path: &PathBuf| folder_distance(folder, path.parent().unwrap_or(Path::new("")));
+    let exact = video_files.iter().filter(|(_, video)| *video == key).map(|(path, _)| path);
+    if let Some(path) = exact.min_by_key(|path| distance(path)) {
+        return Some(path);
+    }
+    let mut candidates: Vec<(usize, &PathBuf)> = video_files
+        .iter()
+        .filter(|(_, video)| video.matches(&key))
+        .map(|(path, _)| (distance(path), path))
+        .collect();
+    candidates.sort_by_key(|(distance, _)| *distance);
+    match candidates.as_slice() {
+        [(_, path)] => Some(path),
+        [(nearest, path), (next, _), ..] if nearest < next => Some(path),
+        _ => None,
+    }
+}
@@ -0,0 +1,78 @@
+// Errors reported for a run and for the single files in it
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::encoding::Encoding;
+
+/// Why a run, or one file of it, failed. The message leaves out the file the
+/// error is about, which [`SubSyncError::path`] gives.
+#[derive(Debug)]
+pub enum SubSyncError {
+    /// The command line could not be understood.
+    Usage(String),
+    /// A file or folder could not be read, written or removed.
+    Io { path: PathBuf, source: io::Error },
+    /// A subtitle's contents are not valid text in the encoding it was read in.
+    Decode { path: PathBuf, encoding: Encoding },
+    /// A subtitle has a character the encoding it is written in cannot represent.
+    Encode { path: PathBuf, encoding: Encoding, character: char },
+    /// A file name is not valid UTF-8, so no name can be derived from it.
+    FileName(PathBuf),
+    /// The undo journal could not be written or read.
+    Journal(String),
+    /// The subtitle could not be retimed (`--anchor`, `--reference`, `--audio`).
+    Sync(String),
+    /// The episodes of a multi-episode video could not be joined.
+    Merge(String),
+}
+
+impl SubSyncError {
+    /// An [`SubSyncError::Io`] error for `path`, or [`SubSyncError::Decode`]
+    /// when reading it as text failed because it is not UTF-8.
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        let path = path.into();
+        match source.kind() {
+            io::ErrorKind::InvalidData => SubSyncError::Decode { path, encoding: Encoding::Utf8 },
+            _ => SubSyncError::Io { path, source },
+        }
+    }
+    
+    /// The file the error is about, if it is about a single file.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            SubSyncError::Io { path, .. }
+            | SubSyncError::Decode { path, .. }
+            | SubSyncError::Encode { path, .. }
+            | SubSyncError::FileName(path) => Some(path),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SubSyncError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SubSyncError::Usage(message) => f.write_str(message),
+            SubSyncError::Io { source, .. } => write!(f, "{}", source),
+            SubSyncError::Decode { encoding, .. } => write!(f, "not valid {} text", encoding),
+            SubSyncError::Encode { encoding, character, .. } => {
+                write!(f, "'{}' (U+{:04X}) cannot be written in {}", character, *character as u32, encoding)
+            }
+            SubSyncError::FileName(_) => f.write_str("file name is not valid UTF-8"),
+            SubSyncError::Journal(message) => write!(f, "undo journal: {}", message),
+            SubSyncError::Sync(message) => write!(f, "cannot sync: {}", message),
+            SubSyncError::Merge(message) => write!(f, "cannot merge: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SubSyncError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SubSyncError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// File extensions recognized as videos and subtitles
+
+use crate::subtitle::SubtitleFormat;
+
+/// Video extensions recognized unless removed.
+pub const DEFAULT_VIDEO_EXTENSIONS: &[&str] = &[
+    "mkv", "mp4", "m4v", "avi", "webm", "mov", "wmv", "ts", "m2ts", "mts", "ogm", "ogv", "flv", "mpg", "mpeg", "vob",
+    "divx", "rmvb",
+];
+
+/// Subtitle extensions recognized unless removed, with the format they are read as.
+pub const DEFAULT_SUBTITLE_EXTENSIONS: &[(&str, SubtitleFormat)] =
+    &[("srt", SubtitleFormat::Srt), ("ass", SubtitleFormat::Ass), ("vtt", SubtitleFormat::Vtt)];
+
+/// Normalizes an extension as given by a user: lowercase, without a leading dot.
+fn normalize(ext: &str) -> String {
+    ext.trim().trim_start_matches('.').to_lowercase()
+}
+
+/// Which files are taken for videos and which for subtitles, by extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extensions {
+    video: Vec<String>,
+    subtitle: Vec<(String, SubtitleFormat)>,
+}
+
+impl Default for Extensions {
+    fn default() -> Self {
+        Extensions {
+            video: DEFAULT_VIDEO_EXTENSIONS.iter().map(|ext| ext.to_string()).collect(),
+            subtitle: DEFAULT_SUBTITLE_EXTENSIONS.iter().map(|&(ext, format)| (ext.to_string(), format)).collect(),
+        }
+    }
+}
+
+impl Extensions {
+    /// Video extensions, in the order they were added.
+    pub fn video(&self) -> impl Iterator<Item = &str> {
+        self.video.iter().map(String::as_str)
+    }
+    
+    /// Subtitle extensions and the format each is read as.
+    pub fn subtitle(&self) -> impl Iterator<Item = (&str, SubtitleFormat)> {
+        self.subtitle.iter().map(|(ext, format)| (ext.as_str(), *format))
+    }
+    
+    /// Recognizes `ext` as a video extension; it stops being a subtitle one.
+    pub fn add_video(&mut self, ext: &str) {
+        let ext = normalize(ext);
+        self.remove(&ext);
+        self.video.push(ext);
+    }
+    
+    /// Recognizes `ext` as a subtitle extension read as `format` (e.g. `ssa` as ASS);
+    /// it stops being a video one.
+    pub fn add_subtitle(&mut self, ext: &str, format: SubtitleFormat) {
+        let ext = normalize(ext);
+        self.remove(&ext);
+        self.subtitle.push((ext, format));
+    }
+    
+    /// Stops recognizing `ext` as either kind of file.
+    pub fn remove(&mut self, ext: &str) {
+        let ext = normalize(ext);
+        self.video.retain(|video| *video != ext);
+        self.subtitle.retain(|(subtitle, _)| *subtitle != ext);
+    }
+    
+    pub fn is_video(&self, ext: &str) -> bool {
+        let ext = normalize(ext);
+        self.video.contains(&ext)
+    }
+    
+    /// The format a subtitle with this extension is read as, if it is one.
+    pub fn subtitle_format(&self, ext: &str) -> Option<SubtitleFormat> {
+        let ext = normalize(ext);
+        self.subtitle.iter().find(|(subtitle, _)| *subtitle == ext).map(|(_, format)| *format)
+    }
+}
@@ -0,0 +1,287 @@
+// Undo journal: records every file SubSync writes or removes, with backups
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Folder, inside the processed folder, holding one sub-folder per run.
+pub const JOURNAL_DIR: &str = ".subsync";
+
+const JOURNAL_FILE: &str = "journal";
+const JOURNAL_HEADER: &str = "subsync-journal 1";
+
+/// 64-bit FNV-1a hash of `bytes`, as 16 hex digits.
+pub fn content_hash(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// One recorded file operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalEntry {
+    /// An existing file was about to be overwritten; its content is in `backup`.
+    Replaced { path: PathBuf, hash: String, backup: String },
+    /// A file was written with content hashing to `hash`.
+    Created { path: PathBuf, hash: String },
+    /// A file was deleted; its content is in `backup`.
+    Removed { path: PathBuf, hash: String, backup: String },
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n")
+}
+
+fn unescape(s: &str) -> String {
+    let mut result = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+    result
+}
+
+impl JournalEntry {
+    fn to_line(&self) -> String {
+        let path = |p: &Path| escape(&p.to_string_lossy());
+        match self {
+            JournalEntry::Replaced { path: p, hash, backup } => format!("replaced\t{}\t{}\t{}", path(p), hash, escape(backup)),
+            JournalEntry::Created { path: p, hash } => format!("created\t{}\t{}", path(p), hash),
+            JournalEntry::Removed { path: p, hash, backup } => format!("removed\t{}\t{}\t{}", path(p), hash, escape(backup)),
+        }
+    }
+    
+    fn parse(line: &str) -> Option<Self> {
+        let fields: Vec<String> = line.split('\t').map(unescape).collect();
+        match fields.as_slice() {
+            [kind, path, hash, backup] if kind == "replaced" => Some(JournalEntry::Replaced {
+                path: PathBuf::from(path),
+                hash: hash.clone(),
+                backup: backup.clone(),
+            }),
+            [kind, path, hash] if kind == "created" => Some(JournalEntry::Created { path: PathBuf::from(path), hash: hash.clone() }),
+            [kind, path, hash, backup] if kind == "removed" => Some(JournalEntry::Removed {
+                path: PathBuf::from(path),
+                hash: hash.clone(),
+                backup: backup.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Length of a run time, `YYYYMMDD-HHMMSS`.
+const RUN_TIME_LEN: usize = 15;
+
+/// Formats a Unix timestamp as `YYYYMMDD-HHMMSS` (UTC).
+fn format_run_time(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+/// Sort key of a run id: its time, then the `-N` suffix of runs started in
+/// the same second, compared as a number so `-10` comes after `-2`.
+fn run_order(run_id: &str) -> (&str, u32) {
+    let suffix = run_id.get(RUN_TIME_LEN..).and_then(|rest| rest.strip_prefix('-')).and_then(|n| n.parse().ok());
+    match suffix {
+        Some(attempt) => (&run_id[..RUN_TIME_LEN], attempt),
+        None => (run_id, 1),
+    }
+}
+
+/// Journal of the run in progress. Entries are appended and flushed as each
+/// operation happens, so even an interrupted run can be undone.
+pub struct Journal {
+    root: PathBuf,
+    dir: PathBuf,
+    run_id: String,
+    file: File,
+    backups: usize,
+}
+
+impl Journal {
+    /// Starts a new run journal for the folder `root`.
+    pub fn create(root: &Path) -> io::Result<Self> {
+        let base = root.join(JOURNAL_DIR);
+        fs::create_dir_all(&base)?;
+        
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let stamp = format_run_time(secs);
+        let mut run_id = stamp.clone();
+        let mut attempt = 1;
+        while base.join(&run_id).exists() {
+            attempt += 1;
+            run_id = format!("{}-{}", stamp, attempt);
+        }
+        
+        let dir = base.join(&run_id);
+        fs::create_dir(&dir)?;
+        let mut file = File::create(dir.join(JOURNAL_FILE))?;
+        writeln!(file, "{}", JOURNAL_HEADER)?;
+        
+        Ok(Journal { root: root.to_path_buf(), dir, run_id, file, backups: 0 })
+    }
+    
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+    
+    fn relative(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.root).unwrap_or(path).to_path_buf()
+    }
+    
+    fn record(&mut self, entry: JournalEntry) -> io::Result<()> {
+        writeln!(self.file, "{}", entry.to_line())?;
+        self.file.sync_data()
+    }
+    
+    /// Copies `path` into the run's backup folder, returning the backup name and content hash.
+    fn backup(&mut self, path: &Path) -> io::Result<(String, String)> {
+        let content = fs::read(path)?;
+        self.backups += 1;
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let backup = format!("{:04}-{}", self.backups, name);
+        fs::write(self.dir.join(&backup), &content)?;
+        Ok((backup, content_hash(&content)))
+    }
+    
+    /// Writes `contents` to `path`, backing up any file it replaces.
+    pub fn write_file(&mut self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        if path.exists() {
+            let (backup, hash) = self.backup(path)?;
+            let path = self.relative(path);
+            self.record(JournalEntry::Replaced { path, hash, backup })?;
+        }
+        fs::write(path, contents)?;
+        let path = self.relative(path);
+        self.record(JournalEntry::Created { path, hash: content_hash(contents) })
+    }
+    
+    /// Backs up `path` and then deletes it.
+    pub fn remove_file(&mut self, path: &Path) -> io::Result<()> {
+        let (backup, hash) = self.backup(path)?;
+        fs::remove_file(path)?;
+        let path = self.relative(path);
+        self.record(JournalEntry::Removed { path, hash, backup })
+    }
+}
+
+/// Run ids with a journal in `root`, oldest first.
+pub fn list_runs(root: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(root.join(JOURNAL_DIR)) else {
+        return Vec::new();
+    };
+    let mut runs: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join(JOURNAL_FILE).is_file())
+        .filter_map(|entry| entry.file_name().to_str().map(String::from))
+        .collect();
+    runs.sort_by(|a, b| run_order(a).cmp(&run_order(b)));
+    runs
+}
+
+/// Reads the entries of a run's journal.
+pub fn read_journal(root: &Path, run_id: &str) -> Result<Vec<JournalEntry>, String> {
+    let path = root.join(JOURNAL_DIR).join(run_id).join(JOURNAL_FILE);
+    let content = fs::read_to_string(&path).map_err(|e| format!("cannot read journal '{}': {}", path.display(), e))?;
+    let mut lines = content.lines();
+    if lines.next() != Some(JOURNAL_HEADER) {
+        return Err(format!("'{}' is not a SubSync journal", path.display()));
+    }
+    lines
+        .filter(|line| !line.is_empty())
+        .map(|line| JournalEntry::parse(line).ok_or_else(|| format!("invalid journal line: {}", line)))
+        .collect()
+}
+
+/// What an undo did.
+#[derive(Debug, Default)]
+pub struct UndoReport {
+    pub run_id: String,
+    pub deleted: Vec<PathBuf>,
+    pub restored: Vec<PathBuf>,
+    /// Entries that were left alone because the files changed after the run.
+    pub conflicts: Vec<String>,
+}
+
+/// Reverts a run (the latest one if `run_id` is `None`), replaying its journal
+/// backwards. `run_id` must be one of the runs [`list_runs`] finds.
+///
+/// Files SubSync wrote are deleted only if they still have the content it
+/// wrote, and backups are only restored onto paths that are free, so edits
+/// made after the run are never lost. The run's journal and backups are
+/// deleted once everything was restored.
+pub fn undo(root: &Path, run_id: Option<&str>) -> Result<UndoReport, String> {
+    // Only runs found in the journal folder, so the id cannot point outside it
+    let mut runs = list_runs(root);
+    let run_id = match run_id {
+        Some(id) => runs
+            .into_iter()
+            .find(|run| run == id)
+            .ok_or_else(|| format!("no SubSync run '{}' in '{}'", id, root.display()))?,
+        None => runs.pop().ok_or_else(|| format!("no SubSync runs to undo in '{}'", root.display()))?,
+    };
+    let entries = read_journal(root, &run_id)?;
+    let dir = root.join(JOURNAL_DIR).join(&run_id);
+    let mut report = UndoReport { run_id, ..UndoReport::default() };
+    
+    for entry in entries.iter().rev() {
+        match entry {
+            JournalEntry::Created { path, hash } => {
+                let full = root.join(path);
+                match fs::read(&full) {
+                    Ok(content) if content_hash(&content) == *hash => {
+                        fs::remove_file(&full).map_err(|e| format!("cannot delete '{}': {}", full.display(), e))?;
+                        report.deleted.push(path.clone());
+                    }
+                    Ok(_) => report.conflicts.push(format!("{} was modified after the run, kept it", path.display())),
+                    Err(_) => {}
+                }
+            }
+            JournalEntry::Replaced { path, hash, backup } | JournalEntry::Removed { path, hash, backup } => {
+                let full = root.join(path);
+                if fs::read(&full).is_ok_and(|content| content_hash(&content) == *hash) {
+                    // Already restored by an earlier, partial undo
+                    continue;
+                }
+                if full.exists() {
+                    report.conflicts.push(format!("{} exists, not restoring the backup over it", path.display()));
+                    continue;
+                }
+                fs::copy(dir.join(backup), &full).map_err(|e| format!("cannot restore '{}': {}", full.display(), e))?;
+                report.restored.push(path.clone());
+            }
+        }
+    }
+    
+    if report.conflicts.is_empty() {
+        fs::remove_dir_all(&dir).map_err(|e| format!("cannot remove '{}': {}", dir.display(), e))?;
+    }
+    Ok(report)
+}
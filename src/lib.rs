@@ -0,0 +1,71 @@
+// SubSync - Subtitle Synchronization & Batch Renaming Tool
+// Library API: subtitle parsing/serialization, timing shifts and episode matching
+
+pub mod align;
+pub mod ass;
+pub mod audio;
+pub mod convert;
+pub mod encoding;
+mod encoding_tables;
+pub mod episode;
+pub mod error;
+pub mod extensions;
+pub mod journal;
+pub mod merge;
+pub mod movie;
+pub mod release;
+pub mod seasons;
+pub mod srt;
+pub mod subtitle;
+pub mod sync;
+pub mod tags;
+pub mod timestamp;
+pub mod timing;
+pub mod vad;
+pub mod vtt;
+
+pub use align::Alignment;
+pub use convert::ConvertOptions;
+pub use episode::{
+    episode_key, extract_episode, extract_episode_number, find_matching_video, find_nearest_video, mapped_episode_key,
+    EpisodeKey,
+};
+pub use encoding::{DecodedText, Encoding};
+pub use error::SubSyncError;
+pub use extensions::Extensions;
+pub use journal::{Journal, UndoReport};
+pub use movie::{find_movie_video, movie_similarity};
+pub use release::{EpisodeRule, ReleaseInfo};
+pub use seasons::SeasonMap;
+pub use srt::{LineIssue, ParseWarning};
+pub use subtitle::{
+    AssEvent, Block, Cue, CueMeta, SourceLine, SrtCoordinates, SubtitleDocument, SubtitleFormat, TextLayout,
+};
+pub use sync::{Anchor, AnchorMode, AnchorPoint, PiecewiseMap};
+pub use tags::{Language, LanguageStyle, SubtitleFlag, SubtitleTags};
+pub use timing::{LinearMap, Ratio};
+pub use timestamp::{
+    format_timestamp_ass, format_timestamp_srt, format_timestamp_vtt, parse_timestamp_ass, parse_timestamp_any,
+    parse_timestamp_srt, parse_timestamp_vtt,
+};
+
+/// Shifts every timestamp of an SRT file by `shift_ms`.
+pub fn shift_srt(content: &str, shift_ms: i64) -> String {
+    let mut doc = srt::parse(content);
+    doc.shift(shift_ms);
+    srt::serialize(&doc)
+}
+
+/// Shifts every dialogue timestamp of an ASS file by `shift_ms`.
+pub fn shift_ass(content: &str, shift_ms: i64) -> String {
+    let mut doc = ass::parse(content);
+    doc.shift(shift_ms);
+    ass::serialize(&doc)
+}
+
+/// Shifts every cue timestamp of a WebVTT file by `shift_ms`.
+pub fn shift_vtt(content: &str, shift_ms: i64) -> String {
+    let mut doc = vtt::parse(content);
+    doc.shift(shift_ms);
+    vtt::serialize(&doc)
+}
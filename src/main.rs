@@ -1,214 +1,855 @@
 // SubSync - Subtitle Synchronization & Batch Renaming Tool
 // A tool for shifting subtitle timestamps and renaming them to match video files
 
+mod cli;
+
+use cli::{Command, ConflictPolicy, Options, SyncMode};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use regex::Regex;
+use subsync::align::{align, speech_intervals, AlignOptions};
+use subsync::audio::load_audio;
+use subsync::encoding::encode_text;
+use subsync::srt;
+use subsync::vad::{detect_speech, VadOptions};
+use subsync::{
+    find_matching_video, find_movie_video, find_nearest_video, format_timestamp_srt, mapped_episode_key, Alignment,
+    AnchorMode, ConvertOptions, DecodedText, Encoding, EpisodeKey, Extensions, Journal, LineIssue, LinearMap, Ratio,
+    ReleaseInfo, SeasonMap, SubSyncError, SubtitleDocument, SubtitleFormat, SubtitleTags,
+};
 
-fn parse_timestamp_srt(ts: &str) -> Option<i64> {
-    let parts: Vec<&str> = ts.split(&[':', ','][..]).collect();
-    if parts.len() != 4 {
-        return None;
-    }
-    
-    let hours: i64 = parts[0].parse().ok()?;
-    let minutes: i64 = parts[1].parse().ok()?;
-    let seconds: i64 = parts[2].parse().ok()?;
-    let millis: i64 = parts[3].parse().ok()?;
+/// Number of cues shown before/after in the dry-run timing preview.
+const PREVIEW_CUES: usize = 3;
+
+/// Exit code when nothing could be done: every file failed, or the folder could
+/// not be read, or an option file or the journal could not be used.
+const EXIT_FAILURE: i32 = 1;
+/// Exit code for a command line that could not be understood, or whose folder
+/// is not a directory.
+const EXIT_USAGE: i32 = 2;
+/// Exit code when some files failed and the others were processed.
+const EXIT_PARTIAL_FAILURE: i32 = 3;
+
+/// A subtitle scheduled for processing and where its output goes.
+struct Job {
+    sub_path: PathBuf,
+    /// `None` for a movie (`--movies`).
+    episode: Option<EpisodeKey>,
+    format: SubtitleFormat,
+    video: Option<PathBuf>,
+    target: PathBuf,
+    /// What happened to this job's target because of a collision.
+    collision: Option<String>,
+    skip: bool,
+    /// Later episodes of a multi-episode video joined onto this one (`--merge-episodes`).
+    merged: Vec<Part>,
+}
+
+/// A further episode's subtitle, appended to a job's output.
+struct Part {
+    sub_path: PathBuf,
+    episode: Option<EpisodeKey>,
+    format: SubtitleFormat,
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+/// The file name without its extension, or an empty string if it isn't UTF-8.
+fn file_stem(path: &Path) -> &str {
+    path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("")
+}
+
+/// Folds the jobs for the episodes of one multi-episode video into the job
+/// of its first episode, so they are written as a single file.
+fn merge_multi_episode_jobs(jobs: Vec<Job>, video_files: &[(PathBuf, EpisodeKey)]) -> Vec<Job> {
+    let is_multi_episode = |video: &Path| video_files.iter().any(|(path, key)| path == video && key.is_range());
+    let mut result: Vec<Job> = Vec::with_capacity(jobs.len());
     
-    Some(hours * 3600000 + minutes * 60000 + seconds * 1000 + millis)
+    for mut job in jobs {
+        let leader = result.iter_mut().find(|other| {
+            other.video.is_some() && other.video == job.video && job.video.as_deref().is_some_and(is_multi_episode)
+        });
+        let Some(leader) = leader else {
+            result.push(job);
+            continue;
+        };
+        if job.episode < leader.episode {
+            std::mem::swap(&mut leader.sub_path, &mut job.sub_path);
+            std::mem::swap(&mut leader.episode, &mut job.episode);
+            std::mem::swap(&mut leader.format, &mut job.format);
+        }
+        leader.merged.push(Part { sub_path: job.sub_path, episode: job.episode, format: job.format });
+        leader.merged.sort_by_key(|part| part.episode);
+    }
+    result
 }
 
-fn parse_timestamp_ass(ts: &str) -> Option<i64> {
-    // ASS format: H:MM:SS.CC (centiseconds, not milliseconds)
-    let parts: Vec<&str> = ts.split(&[':', '.'][..]).collect();
-    if parts.len() != 4 {
-        return None;
+/// The subtitles of a run, read before the first write so that a job writing
+/// over another's source (`--on-conflict overwrite`) cannot change what that
+/// job reads. Those that cannot be read are left out and fail in their job.
+struct Sources(HashMap<PathBuf, Vec<u8>>);
+
+impl Sources {
+    fn read(jobs: &[Job]) -> Self {
+        let paths = jobs
+            .iter()
+            .filter(|job| !job.skip)
+            .flat_map(|job| std::iter::once(&job.sub_path).chain(job.merged.iter().map(|part| &part.sub_path)));
+        Sources(paths.filter_map(|path| Some((path.clone(), fs::read(path).ok()?))).collect())
     }
     
-    let hours: i64 = parts[0].parse().ok()?;
-    let minutes: i64 = parts[1].parse().ok()?;
-    let seconds: i64 = parts[2].parse().ok()?;
-    let centiseconds: i64 = parts[3].parse().ok()?;
-    
-    Some(hours * 3600000 + minutes * 60000 + seconds * 1000 + centiseconds * 10)
+    /// Reads a subtitle as text, in `encoding` or else the one detected for it.
+    fn read_text(&self, path: &Path, encoding: Option<Encoding>) -> Result<DecodedText, SubSyncError> {
+        let read;
+        let bytes = match self.0.get(path) {
+            Some(bytes) => bytes,
+            None => {
+                read = fs::read(path).map_err(|err| SubSyncError::io(path, err))?;
+                &read
+            }
+        };
+        DecodedText::decode(bytes, encoding).map_err(|encoding| SubSyncError::Decode { path: path.to_path_buf(), encoding })
+    }
 }
 
-fn format_timestamp_srt(ms: i64) -> String {
-    let hours = ms / 3600000;
-    let minutes = (ms % 3600000) / 60000;
-    let seconds = (ms % 60000) / 1000;
-    let millis = ms % 1000;
-    
-    format!("{:02}:{:02}:{:02},{:03}", hours, minutes, seconds, millis)
+/// Parses a subtitle, reporting the SRT lines that could not be parsed and, with
+/// `verbose`, each one that had to be repaired (else just how many).
+fn parse_subtitle(path: &Path, text: &str, format: SubtitleFormat, verbose: bool) -> SubtitleDocument {
+    if format != SubtitleFormat::Srt {
+        return SubtitleDocument::parse(text, format);
+    }
+    let (doc, warnings) = srt::parse_with_warnings(text);
+    let repaired = warnings.iter().filter(|warning| warning.issue == LineIssue::Repaired).count();
+    for warning in &warnings {
+        if verbose || warning.issue != LineIssue::Repaired {
+            println!("  ⚠ {}: {}", file_name(path), warning);
+        }
+    }
+    if !verbose && repaired > 0 {
+        println!("  ⚠ {}: repaired {} malformed timing line(s), --verbose lists them", file_name(path), repaired);
+    }
+    doc
 }
 
-fn format_timestamp_ass(ms: i64) -> String {
-    let hours = ms / 3600000;
-    let minutes = (ms % 3600000) / 60000;
-    let seconds = (ms % 60000) / 1000;
-    let centiseconds = (ms % 1000) / 10;
-    
-    format!("{}:{:02}:{:02}.{:02}", hours, minutes, seconds, centiseconds)
+fn last_cue_end(doc: &SubtitleDocument) -> i64 {
+    doc.cues().map(|cue| cue.end).max().unwrap_or(0)
+}
+
+/// Retimes the merged parts of `job`, each on its own timeline, and appends
+/// them to `doc`. Each part is then moved behind the previous ones: by
+/// `--episode-duration` per episode, or else to where the previous subtitle's
+/// last cue ended before retiming (`first_end` for the job's own subtitle).
+fn append_parts(
+    doc: &mut SubtitleDocument,
+    first_end: i64,
+    job: &Job,
+    sources: &Sources,
+    options: &Options,
+    references: &[(PathBuf, Option<EpisodeKey>)],
+    convert_options: &ConvertOptions,
+) -> Result<(), SubSyncError> {
+    let mut previous_end = first_end;
+    let mut offset = 0;
+    for (i, part) in job.merged.iter().enumerate() {
+        let source = sources.read_text(&part.sub_path, options.input_encoding)?;
+        let mut part_doc = parse_subtitle(&part.sub_path, &source.text, part.format, options.verbose);
+        offset = match options.episode_duration_ms {
+            Some(duration) => duration * (i as i64 + 1),
+            None => offset + previous_end,
+        };
+        previous_end = last_cue_end(&part_doc);
+        
+        println!("  Merging: {} at +{}", file_name(&part.sub_path), format_timestamp_srt(offset));
+        retime(&mut part_doc, part.episode, options, references).map_err(SubSyncError::Merge)?;
+        part_doc.shift(offset);
+        doc.append(&part_doc, convert_options);
+    }
+    Ok(())
 }
 
-fn shift_srt(content: &str, shift_ms: i64) -> String {
-    let mut result = String::new();
+/// Several subtitles whose output would land on the same path.
+struct Collision {
+    target: PathBuf,
+    /// Indices of the jobs writing to `target`: first the one whose source it
+    /// is, if any, then the others in processing order.
+    jobs: Vec<usize>,
+    /// `target` already exists and is not the source of any of these jobs.
+    exists: bool,
+}
+
+/// Groups the jobs by target path and returns every group that would clobber a file.
+fn find_collisions(jobs: &[Job]) -> Vec<Collision> {
+    let mut groups: BTreeMap<&Path, Vec<usize>> = BTreeMap::new();
+    for (i, job) in jobs.iter().enumerate() {
+        groups.entry(&job.target).or_default().push(i);
+    }
+    groups
+        .into_iter()
+        .filter_map(|(target, mut indices)| {
+            // A subtitle that already has the name keeps it
+            let owner = indices.iter().position(|&i| same_file(target, &jobs[i].sub_path));
+            if let Some(owner) = owner {
+                let index = indices.remove(owner);
+                indices.insert(0, index);
+            }
+            let exists = target.exists() && owner.is_none();
+            (indices.len() > 1 || exists).then(|| Collision { target: target.to_path_buf(), jobs: indices, exists })
+        })
+        .collect()
+}
+
+/// `<stem>.<n>.<ext>` next to `target`.
+fn suffixed(target: &Path, n: u32) -> PathBuf {
+    let stem = target.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    match target.extension() {
+        Some(ext) => target.with_file_name(format!("{}.{}.{}", stem, n, ext.to_string_lossy())),
+        None => target.with_file_name(format!("{}.{}", stem, n)),
+    }
+}
+
+/// Applies `policy` to the collisions, retargeting or skipping jobs, and
+/// returns one summary line per collision.
+fn resolve_collisions(jobs: &mut [Job], collisions: &[Collision], policy: ConflictPolicy) -> Vec<String> {
+    let mut taken: HashSet<PathBuf> = jobs.iter().map(|job| job.target.clone()).collect();
+    let mut summary = Vec::new();
     
-    for line in content.lines() {
-        if line.contains(" --> ") {
-            let parts: Vec<&str> = line.split(" --> ").collect();
-            if parts.len() == 2 {
-                if let (Some(start_ms), Some(end_ms)) = (parse_timestamp_srt(parts[0]), parse_timestamp_srt(parts[1])) {
-                    let new_start = (start_ms + shift_ms).max(0);
-                    let new_end = (end_ms + shift_ms).max(0);
-                    result.push_str(&format!("{} --> {}\n", format_timestamp_srt(new_start), format_timestamp_srt(new_end)));
-                    continue;
+    for collision in collisions {
+        let target_name = file_name(&collision.target);
+        let sources: Vec<String> = collision.jobs.iter().map(|&i| file_name(&jobs[i].sub_path)).collect();
+        let mut line = if collision.exists {
+            format!("{} already exists; wanted by {}", target_name, sources.join(", "))
+        } else {
+            format!("{} wanted by {}", target_name, sources.join(", "))
+        };
+        
+        // Jobs that give up the name: all of them if a file already has it, else all but the first
+        let losers = if collision.exists { &collision.jobs[..] } else { &collision.jobs[1..] };
+        match policy {
+            ConflictPolicy::Overwrite => {
+                line.push_str(" → overwritten");
+                for &i in &collision.jobs {
+                    jobs[i].collision = Some(format!("{} is overwritten", target_name));
+                }
+            }
+            ConflictPolicy::Skip => {
+                line.push_str(" → skipped ");
+                line.push_str(&losers.iter().map(|&i| file_name(&jobs[i].sub_path)).collect::<Vec<_>>().join(", "));
+                for &i in losers {
+                    jobs[i].skip = true;
+                    jobs[i].collision = Some(format!("{} is taken, skipped", target_name));
                 }
             }
+            ConflictPolicy::Suffix => {
+                let mut n = 2;
+                let mut renames = Vec::new();
+                for &i in losers {
+                    let target = loop {
+                        let candidate = suffixed(&collision.target, n);
+                        n += 1;
+                        if !candidate.exists() && !taken.contains(&candidate) {
+                            break candidate;
+                        }
+                    };
+                    taken.insert(target.clone());
+                    renames.push(format!("{} to {}", file_name(&jobs[i].sub_path), file_name(&target)));
+                    jobs[i].collision = Some(format!("{} is taken, renamed to {}", target_name, file_name(&target)));
+                    jobs[i].target = target;
+                }
+                line.push_str(&format!(" → renamed {}", renames.join(", ")));
+            }
+            ConflictPolicy::Fail => {}
         }
-        result.push_str(line);
-        result.push('\n');
+        summary.push(line);
     }
-    
-    result
+    summary
 }
 
-fn shift_ass(content: &str, shift_ms: i64) -> String {
-    let dialogue_re = Regex::new(r"^(Dialogue: \d+,)(\d+:\d+:\d+\.\d+),(\d+:\d+:\d+\.\d+),(.+)$").unwrap();
-    let mut result = String::new();
-    
-    for line in content.lines() {
-        if let Some(caps) = dialogue_re.captures(line) {
-            let prefix = &caps[1];
-            let start = &caps[2];
-            let end = &caps[3];
-            let rest = &caps[4];
+/// The numbers read from a file name, for --verbose: `season 1, episodes 5-6, version 2`.
+fn extracted_numbers(info: &ReleaseInfo) -> String {
+    let mut parts = Vec::new();
+    if let Some(season) = info.season {
+        parts.push(format!("season {}", season));
+    }
+    match (info.episode, info.last_episode) {
+        (Some(episode), Some(last)) if last != episode => parts.push(format!("episodes {}-{}", episode, last)),
+        (Some(episode), _) => parts.push(format!("episode {}", episode)),
+        (None, _) => {}
+    }
+    if let Some(version) = info.version {
+        parts.push(format!("version {}", version));
+    }
+    parts.join(", ")
+}
+
+/// `path` relative to the scanned folder, for messages.
+fn relative_name(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path).display().to_string()
+}
+
+/// Lists the files in `folder`, with `recursive` including those in its
+/// subfolders. Hidden folders, such as the `.subsync` journal, are left out.
+/// Subfolders that cannot be read are added to `skipped` with the reason.
+fn list_files(folder: &Path, recursive: bool, skipped: &mut Vec<(PathBuf, String)>) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut folders = Vec::new();
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            files.push(path);
+        } else if recursive && !file_name(&path).starts_with('.') {
+            folders.push(path);
+        }
+    }
+    for subfolder in folders {
+        match list_files(&subfolder, recursive, skipped) {
+            Ok(subfolder_files) => files.extend(subfolder_files),
+            Err(err) => skipped.push((subfolder, format!("unreadable folder: {}", err))),
+        }
+    }
+    Ok(files)
+}
+
+fn load_subtitle(path: &Path, extensions: &Extensions) -> Option<SubtitleDocument> {
+    let format = extensions.subtitle_format(path.extension()?.to_str()?)?;
+    let source = DecodedText::decode(&fs::read(path).ok()?, None).ok()?;
+    Some(SubtitleDocument::parse(&source.text, format))
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn is_audio_extension(ext: &str) -> bool {
+    matches!(ext.to_lowercase().as_str(), "wav" | "flac")
+}
+
+/// Lists the references for `--reference`/`--audio`: the file itself, or every
+/// file of the accepted type in the folder together with its episode number.
+fn find_references(
+    path: &Path,
+    accept: &dyn Fn(&str) -> bool,
+    season_map: Option<&SeasonMap>,
+    recursive: bool,
+) -> Vec<(PathBuf, Option<EpisodeKey>)> {
+    if path.is_file() {
+        return vec![(path.to_path_buf(), None)];
+    }
+    let Ok(files) = list_files(path, recursive, &mut Vec::new()) else {
+        return Vec::new();
+    };
+    files
+        .into_iter()
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(accept)
+        })
+        .filter_map(|path| {
+            let episode = mapped_episode_key(&path, season_map)?;
+            Some((path, Some(episode)))
+        })
+        .collect()
+}
+
+fn find_reference(references: &[(PathBuf, Option<EpisodeKey>)], episode: Option<EpisodeKey>) -> Result<PathBuf, String> {
+    if let [(path, None)] = references {
+        return Ok(path.clone());
+    }
+    let episode = episode.ok_or("movies need a single reference file rather than a folder")?;
+    let keyed: Vec<(PathBuf, EpisodeKey)> =
+        references.iter().filter_map(|(path, key)| Some((path.clone(), (*key)?))).collect();
+    find_matching_video(&keyed, episode)
+        .cloned()
+        .ok_or_else(|| format!("no reference for episode {}", episode))
+}
+
+/// Prints a detected alignment and applies it through the regular shift path.
+fn apply_alignment(doc: &mut SubtitleDocument, alignment: &Alignment) {
+    println!(
+        "  Detected: shift {:+.3} seconds, scale {}, confidence {:.0}%",
+        alignment.offset_ms as f64 / 1000.0,
+        alignment.scale,
+        alignment.confidence * 100.0
+    );
+    if alignment.confidence < 0.5 {
+        println!("  ⚠ Low confidence, check this file by hand");
+    }
+    doc.apply_linear(&alignment.to_linear_map());
+}
+
+/// Applies the chosen sync mode to one subtitle, printing what was done.
+fn retime(
+    doc: &mut SubtitleDocument,
+    episode: Option<EpisodeKey>,
+    options: &Options,
+    references: &[(PathBuf, Option<EpisodeKey>)],
+) -> Result<(), String> {
+    match &options.sync {
+        SyncMode::Shift { scale, shift_seconds } => {
+            doc.apply_linear(&LinearMap::new(*scale, (shift_seconds * 1000.0) as i64));
+        }
+        SyncMode::Anchors { anchors, mode } => {
+            let map = doc.anchor_sync(anchors, *mode)?;
+            let offsets: Vec<String> = map.points().iter().map(|(old, new)| format!("{:+} ms", new - old)).collect();
+            println!("  Sync: offsets at anchors {}", offsets.join(", "));
+            doc.apply_piecewise(&map);
+        }
+        SyncMode::Reference { detect_scale, detect_drift, max_offset_ms, .. } => {
+            let reference_path = find_reference(references, episode)?;
+            let reference = load_subtitle(&reference_path, &options.extensions)
+                .ok_or_else(|| format!("cannot read reference '{}'", reference_path.display()))?;
             
-            if let (Some(start_ms), Some(end_ms)) = (parse_timestamp_ass(start), parse_timestamp_ass(end)) {
-                let new_start = (start_ms + shift_ms).max(0);
-                let new_end = (end_ms + shift_ms).max(0);
-                result.push_str(&format!("{}{},{},{}\n", prefix, format_timestamp_ass(new_start), format_timestamp_ass(new_end), rest));
-                continue;
-            }
+            let align_options = AlignOptions {
+                max_offset_ms: *max_offset_ms,
+                detect_scale: *detect_scale,
+                detect_drift: *detect_drift,
+            };
+            let alignment = doc.align_to(&reference, &align_options).ok_or("no cues to align")?;
+            apply_alignment(doc, &alignment);
+        }
+        SyncMode::Audio { detect_scale, max_offset_ms, .. } => {
+            let audio_path = find_reference(references, episode)?;
+            let audio = load_audio(&audio_path)?;
+            let speech = detect_speech(&audio, &VadOptions::default());
+            println!(
+                "  Audio: {} speech segments in {:.0} seconds",
+                speech.len(),
+                audio.duration_ms() as f64 / 1000.0
+            );
+            
+            let align_options = AlignOptions {
+                max_offset_ms: *max_offset_ms,
+                detect_scale: *detect_scale,
+                detect_drift: true,
+            };
+            let alignment = align(&speech, &speech_intervals(doc), &align_options).ok_or("no speech to align")?;
+            apply_alignment(doc, &alignment);
         }
-        result.push_str(line);
-        result.push('\n');
     }
+    Ok(())
+}
+
+/// Reverts a run and returns the exit code.
+fn undo(folder_path: &Path, run_id: Option<&str>) -> i32 {
+    let report = match subsync::journal::undo(folder_path, run_id) {
+        Ok(report) => report,
+        Err(message) => {
+            eprintln!("Error: {}", SubSyncError::Journal(message));
+            return EXIT_FAILURE;
+        }
+    };
     
-    result
+    println!("Undoing run {} in {}\n", report.run_id, folder_path.display());
+    for path in &report.deleted {
+        println!("  Removed: {}", path.display());
+    }
+    for path in &report.restored {
+        println!("  ✓ Restored: {}", path.display());
+    }
+    for conflict in &report.conflicts {
+        println!("  ⚠ Skipped: {}", conflict);
+    }
+    if report.conflicts.is_empty() {
+        println!("\n✓ Run {} undone", report.run_id);
+        0
+    } else {
+        println!("\n⚠ Run {} partly undone, its journal and backups were kept", report.run_id);
+        EXIT_PARTIAL_FAILURE
+    }
 }
 
-fn extract_episode_number(filename: &str) -> Option<u32> {
-    // Try multiple patterns to match various naming conventions
-    let patterns = vec![
-        r"(?i)e(\d+)",           // E01, e01
-        r"(?i)ep(\d+)",          // EP01, ep01
-        r"(?i)episode[_\s]*(\d+)", // episode01, episode 01
-        r"[\s\-_](\d{2,3})(?:\.|$|[\s\-_])", // - 001, _001, 001.
-    ];
-    
-    for pattern in patterns {
-        let re = Regex::new(pattern).unwrap();
-        if let Some(caps) = re.captures(filename) {
-            if let Some(num) = caps.get(1).and_then(|m| m.as_str().parse().ok()) {
-                return Some(num);
+/// Retimes, converts and writes one subtitle (or, with `--dry-run`, shows
+/// what would be written). The journal is created on the first write.
+fn process_job(
+    job: &Job,
+    sources: &Sources,
+    options: &Options,
+    references: &[(PathBuf, Option<EpisodeKey>)],
+    convert_options: &ConvertOptions,
+    journal: &mut Option<Journal>,
+) -> Result<(), SubSyncError> {
+    let folder_path = options.folder_path.as_path();
+    let source = sources.read_text(&job.sub_path, options.input_encoding)?;
+    
+    let mut doc = parse_subtitle(&job.sub_path, &source.text, job.format, options.verbose);
+    let before: Vec<(i64, i64)> = doc.cues().take(PREVIEW_CUES).map(|cue| (cue.start, cue.end)).collect();
+    let first_end = last_cue_end(&doc);
+    retime(&mut doc, job.episode, options, references).map_err(SubSyncError::Sync)?;
+    let after: Vec<(i64, i64)> = doc.cues().take(PREVIEW_CUES).map(|cue| (cue.start, cue.end)).collect();
+    append_parts(&mut doc, first_end, job, sources, options, references, convert_options)?;
+    if let Some(target) = options.target_format {
+        doc = doc.convert(target, convert_options);
+    }
+    let shifted_content = doc.serialize();
+    let new_name = relative_name(&job.target, folder_path);
+    
+    // Written back in the encoding it was read in unless another is asked for; a
+    // byte order mark is kept with the encoding, and always written for UTF-16
+    let encoding = options.output_encoding.unwrap_or(source.encoding);
+    let bom = match encoding {
+        _ if encoding == source.encoding => source.bom,
+        Encoding::Utf16Le | Encoding::Utf16Be => true,
+        _ => false,
+    };
+    let shifted_bytes = encode_text(&shifted_content, encoding, bom)
+        .map_err(|character| SubSyncError::Encode { path: job.sub_path.clone(), encoding, character })?;
+    let encoding_note = match encoding {
+        _ if encoding != source.encoding => Some(format!("{} → {}", source.encoding, encoding)),
+        Encoding::Utf8 => None,
+        _ => Some(encoding.to_string()),
+    };
+    
+    if options.dry_run {
+        if let Some(episode) = job.episode {
+            println!("  Episode: {}", episode);
+        }
+        match &job.video {
+            Some(video_path) => println!("  Video: {}", relative_name(video_path, folder_path)),
+            None => println!("  Video: none found"),
+        }
+        println!("  Target: {}", new_name);
+        if let Some(note) = &encoding_note {
+            println!("  Encoding: {}", note);
+        }
+        if !before.is_empty() {
+            println!("  Timing (first {} cues):", before.len());
+            for ((old_start, old_end), (new_start, new_end)) in before.iter().zip(&after) {
+                println!(
+                    "    {} --> {}  =>  {} --> {}",
+                    format_timestamp_srt(*old_start),
+                    format_timestamp_srt(*old_end),
+                    format_timestamp_srt(*new_start),
+                    format_timestamp_srt(*new_end)
+                );
             }
         }
+        return Ok(());
     }
     
-    None
-}
-
-fn find_matching_video(video_files: &[(PathBuf, u32)], episode: u32) -> Option<&PathBuf> {
-    video_files.iter()
-        .find(|(_, ep)| *ep == episode)
-        .map(|(path, _)| path)
+    let journal = match journal {
+        Some(journal) => journal,
+        None => journal.insert(Journal::create(folder_path).map_err(|err| SubSyncError::Journal(err.to_string()))?),
+    };
+    let in_place = same_file(&job.target, &job.sub_path);
+    journal
+        .write_file(&job.target, &shifted_bytes)
+        .map_err(|err| SubSyncError::io(&job.target, err))?;
+    if !in_place {
+        journal.remove_file(&job.sub_path).map_err(|err| SubSyncError::io(&job.sub_path, err))?;
+    }
+    for part in &job.merged {
+        if !same_file(&job.target, &part.sub_path) {
+            journal.remove_file(&part.sub_path).map_err(|err| SubSyncError::io(&part.sub_path, err))?;
+        }
+    }
+    if options.verbose
+        && let Some(note) = &encoding_note
+    {
+        println!("  Encoding: {}", note);
+    }
+    if job.video.is_some() {
+        println!("  ✓ Shifted and renamed to: {}", new_name);
+    } else {
+        println!("  ✓ Shifted (no matching video found): {}", new_name);
+    }
+    Ok(())
 }
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
+    let args: Vec<String> = match std::env::args_os().map(|arg| arg.into_string()).collect() {
+        Ok(args) => args,
+        Err(arg) => {
+            eprintln!("Error: {}", SubSyncError::Usage(format!("argument {:?} is not valid UTF-8", arg)));
+            std::process::exit(EXIT_USAGE);
+        }
+    };
     
-    if args.len() != 3 {
-        eprintln!("Usage: {} <folder_path> <shift_seconds>", args[0]);
-        eprintln!("Example: {} ./subtitles -5.43", args[0]);
-        eprintln!("\nThis will:");
-        eprintln!("  1. Process all subtitle files (.srt, .ass) in the folder");
-        eprintln!("  2. Shift timestamps by the specified amount (negative = earlier)");
-        eprintln!("  3. Rename subtitles to match video files based on episode numbers");
-        std::process::exit(1);
+    let options = match cli::parse_args(&args).map_err(SubSyncError::Usage) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::Help) => {
+            cli::print_usage(&args[0]);
+            return;
+        }
+        Ok(Command::Undo { folder_path, run_id }) => {
+            std::process::exit(undo(&folder_path, run_id.as_deref()));
+        }
+        Err(err) => {
+            eprintln!("Error: {}\n", err);
+            cli::print_usage(&args[0]);
+            std::process::exit(EXIT_USAGE);
+        }
+    };
+    
+    let folder_path = options.folder_path.as_path();
+    
+    let mut convert_options = ConvertOptions::default();
+    if let Some(header_path) = &options.ass_header {
+        match fs::read_to_string(header_path) {
+            Ok(header) => convert_options.ass_header = header,
+            Err(err) => {
+                eprintln!("Error: cannot read ASS header {}: {}", header_path.display(), SubSyncError::io(header_path, err));
+                std::process::exit(EXIT_FAILURE);
+            }
+        }
     }
     
-    let folder_path = Path::new(&args[1]);
-    let shift_seconds: f64 = args[2].parse().expect("Invalid shift value");
-    let shift_ms = (shift_seconds * 1000.0) as i64;
+    let season_map = match &options.episode_map {
+        Some(path) => match SeasonMap::load(path) {
+            Ok(map) => Some(map),
+            Err(message) => {
+                eprintln!("Error: {}", message);
+                std::process::exit(EXIT_FAILURE);
+            }
+        },
+        None => options.season_lengths.clone(),
+    };
     
     if !folder_path.exists() || !folder_path.is_dir() {
         eprintln!("Error: '{}' is not a valid directory", folder_path.display());
-        std::process::exit(1);
+        std::process::exit(EXIT_USAGE);
     }
     
     println!("Scanning folder: {}", folder_path.display());
-    println!("Time shift: {} seconds ({} ms)\n", shift_seconds, shift_ms);
+    let mut references = Vec::new();
+    match &options.sync {
+        SyncMode::Shift { scale, shift_seconds } => {
+            if *scale != Ratio::ONE {
+                println!("Time scale: {}", scale);
+            }
+            println!("Time shift: {} seconds ({} ms)", shift_seconds, (shift_seconds * 1000.0) as i64);
+        }
+        SyncMode::Anchors { anchors, mode } => {
+            let mode = match mode {
+                AnchorMode::Step => "step",
+                AnchorMode::Interpolate => "interpolate",
+            };
+            println!("Time sync: from {} anchor points ({})", anchors.len(), mode);
+        }
+        SyncMode::Reference { path, .. } | SyncMode::Audio { path, .. } => {
+            let is_subtitle = |ext: &str| options.extensions.subtitle_format(ext).is_some();
+            let accept: &dyn Fn(&str) -> bool = match options.sync {
+                SyncMode::Audio { .. } => &is_audio_extension,
+                _ => &is_subtitle,
+            };
+            references = find_references(path, accept, season_map.as_ref(), options.recursive);
+            if references.is_empty() {
+                eprintln!("Error: no reference files found at '{}'", path.display());
+                std::process::exit(EXIT_FAILURE);
+            }
+            println!("Time sync: aligning against {}", path.display());
+        }
+    }
+    if season_map.is_some() {
+        println!("Episode numbering: absolute numbers are mapped to seasons");
+    }
+    if let Some(target) = options.target_format {
+        println!("Converting to: .{}", target.extension());
+    }
+    if let Some(encoding) = options.input_encoding {
+        println!("Reading subtitles as: {}", encoding);
+    }
+    if let Some(encoding) = options.output_encoding {
+        println!("Writing subtitles as: {}", encoding);
+    }
+    if options.dry_run {
+        println!("Dry run: no files will be changed");
+    }
+    println!();
     
-    let entries = fs::read_dir(folder_path).expect("Failed to read directory");
+    // Files left out, with the reason, and files that failed, for the summaries at the end
+    let mut skipped: Vec<(PathBuf, String)> = Vec::new();
+    let mut failures: Vec<(PathBuf, SubSyncError)> = Vec::new();
+    let files = match list_files(folder_path, options.recursive, &mut skipped) {
+        Ok(files) => files,
+        Err(err) => {
+            eprintln!("Error: cannot read '{}': {}", folder_path.display(), err);
+            std::process::exit(EXIT_FAILURE);
+        }
+    };
     
     let mut video_files = Vec::new();
+    let mut movie_files = Vec::new();
     let mut subtitle_files = Vec::new();
+    // How each file's episode or title was read, for --verbose
+    let mut detected: Vec<(PathBuf, String)> = Vec::new();
     
-    for entry in entries {
-        let entry = entry.expect("Failed to read entry");
-        let path = entry.path();
+    for path in files {
+        let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+        let is_video = options.extensions.is_video(ext);
+        let subtitle_format = options.extensions.subtitle_format(ext);
+        if !is_video && subtitle_format.is_none() {
+            skipped.push((path, "unsupported extension".to_string()));
+            continue;
+        }
+        if !is_video && references.iter().any(|(reference, _)| same_file(reference, &path)) {
+            skipped.push((path, "used as the reference".to_string()));
+            continue;
+        }
+        if path.file_name().and_then(|name| name.to_str()).is_none() {
+            failures.push((path.clone(), SubSyncError::FileName(path)));
+            continue;
+        }
         
-        if let Some(ext) = path.extension() {
-            let ext_str = ext.to_str().unwrap_or("").to_lowercase();
-            let filename = path.file_name().unwrap().to_str().unwrap_or("");
-            
-            if let Some(episode) = extract_episode_number(filename) {
-                match ext_str.as_str() {
-                    "mkv" | "mp4" | "avi" => {
-                        video_files.push((path.clone(), episode));
-                    }
-                    "srt" | "ass" => {
-                        subtitle_files.push((path.clone(), episode, ext_str));
-                    }
-                    _ => {}
-                }
+        // Movies are matched by title, without episode numbers
+        let episode = if options.movies {
+            if options.verbose {
+                let info = ReleaseInfo::parse(&file_name(&path));
+                let year = info.year.map(|year| format!(" ({})", year)).unwrap_or_default();
+                detected.push((path.clone(), format!("title '{}'{}", info.title.unwrap_or_default(), year)));
+            }
+            None
+        } else {
+            // In `Subs/<episode name>/2_English.srt` the episode is in the folder name, the number is the track's
+            let parent = path.parent().unwrap_or(folder_path);
+            let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("");
+            let from_folder = options.recursive && parent != folder_path && !SubtitleTags::parse_track(stem).is_empty();
+            let source = if from_folder { parent } else { path.as_path() };
+            let Some(episode) = mapped_episode_key(source, season_map.as_ref()) else {
+                skipped.push((path, "no episode number".to_string()));
+                continue;
+            };
+            if options.verbose {
+                let info = ReleaseInfo::parse(&file_name(source));
+                let numbers = extracted_numbers(&info);
+                let (rule, text) = info.episode_rule.map_or((String::new(), String::new()), |(rule, text)| (rule.to_string(), text));
+                let folder = if from_folder { format!(" in folder '{}'", file_name(source)) } else { String::new() };
+                detected.push((path.clone(), format!("'{}'{} read as {} ({}) → {}", text, folder, numbers, rule, episode)));
             }
+            Some(episode)
+        };
+        
+        if is_video {
+            match episode {
+                Some(episode) => video_files.push((path, episode)),
+                None => movie_files.push(path),
+            }
+        } else if let Some(format) = subtitle_format {
+            subtitle_files.push((path, episode, format));
+        }
+    }
+    
+    if options.verbose && !detected.is_empty() {
+        detected.sort();
+        println!("{}:", if options.movies { "Titles" } else { "Episode numbers" });
+        for (path, description) in &detected {
+            println!("  {}: {}", relative_name(path, folder_path), description);
         }
+        println!();
     }
     
-    println!("Found {} video files", video_files.len());
+    subtitle_files.sort_by(|a, b| a.0.cmp(&b.0));
+    
+    println!("Found {} video files", video_files.len() + movie_files.len());
     println!("Found {} subtitle files\n", subtitle_files.len());
     
-    for (sub_path, episode, ext) in subtitle_files {
-        println!("Processing: {}", sub_path.file_name().unwrap().to_str().unwrap());
-        
-        let content = fs::read_to_string(&sub_path).expect("Failed to read subtitle file");
-        
-        let shifted_content = match ext.as_str() {
-            "srt" => shift_srt(&content, shift_ms),
-            "ass" => shift_ass(&content, shift_ms),
-            _ => content,
-        };
-        
-        if let Some(video_path) = find_matching_video(&video_files, episode) {
-            let video_stem = video_path.file_stem().unwrap().to_str().unwrap();
-            let new_name = format!("{}.{}", video_stem, ext);
-            let new_path = folder_path.join(&new_name);
-            
-            fs::write(&new_path, shifted_content).expect("Failed to write file");
-            fs::remove_file(&sub_path).expect("Failed to remove original file");
-            println!("  ✓ Shifted and renamed to: {}", new_name);
-        } else {
-            let new_name = format!("shifted_{}", sub_path.file_name().unwrap().to_str().unwrap());
-            let new_path = folder_path.join(&new_name);
-            
-            fs::write(&new_path, shifted_content).expect("Failed to write file");
-            fs::remove_file(&sub_path).expect("Failed to remove original file");
-            println!("  ✓ Shifted (no matching video found): {}", new_name);
+    let mut jobs: Vec<Job> = subtitle_files
+        .into_iter()
+        .map(|(sub_path, episode, format)| {
+            let output_format = options.target_format.unwrap_or(format);
+            let sub_folder = sub_path.parent().unwrap_or(folder_path);
+            let video = match episode {
+                Some(episode) => find_nearest_video(&video_files, episode, sub_folder).cloned(),
+                None => find_movie_video(&movie_files, &sub_path).cloned(),
+            };
+            let new_name = match &video {
+                Some(video_path) => {
+                    let video_stem = file_stem(video_path);
+                    let sub_stem = file_stem(&sub_path);
+                    let tags = match SubtitleTags::parse(sub_stem) {
+                        tags if tags.is_empty() => SubtitleTags::parse_track(sub_stem),
+                        tags => tags,
+                    };
+                    format!("{}{}.{}", video_stem, tags.suffix(options.language_style), output_format.extension())
+                }
+                None => {
+                    let sub_name = if output_format == format {
+                        file_name(&sub_path)
+                    } else {
+                        format!("{}.{}", file_stem(&sub_path), output_format.extension())
+                    };
+                    format!("shifted_{}", sub_name)
+                }
+            };
+            let target = video.as_deref().and_then(Path::parent).unwrap_or(sub_folder).join(new_name);
+            Job { sub_path, episode, format, video, target, collision: None, skip: false, merged: Vec::new() }
+        })
+        .collect();
+    if options.merge_episodes {
+        jobs = merge_multi_episode_jobs(jobs, &video_files);
+    }
+    
+    let collisions = find_collisions(&jobs);
+    if !collisions.is_empty() && options.on_conflict == ConflictPolicy::Fail {
+        eprintln!("Error: {} output collision(s), nothing was changed:", collisions.len());
+        for line in resolve_collisions(&mut jobs, &collisions, ConflictPolicy::Fail) {
+            eprintln!("  {}", line);
         }
+        std::process::exit(EXIT_FAILURE);
     }
+    let collision_summary = resolve_collisions(&mut jobs, &collisions, options.on_conflict);
     
-    println!("\n✓ All done!");
-}
\ No newline at end of file
+    let sources = Sources::read(&jobs);
+    let mut journal: Option<Journal> = None;
+    // Files that were tried, so a run where every one failed can be told from a partial failure
+    let mut attempted = failures.len();
+    for job in &jobs {
+        println!("Processing: {}", relative_name(&job.sub_path, folder_path));
+        if let Some(collision) = &job.collision {
+            println!("  ⚠ Collision: {}", collision);
+        }
+        if job.skip {
+            continue;
+        }
+        attempted += 1;
+        if let Err(err) = process_job(job, &sources, &options, &references, &convert_options, &mut journal) {
+            eprintln!("  ✗ {}", err);
+            failures.push((job.sub_path.clone(), err));
+        }
+    }
+    
+    if !collision_summary.is_empty() {
+        println!("\nCollisions ({}):", collision_summary.len());
+        for line in &collision_summary {
+            println!("  {}", line);
+        }
+    }
+    
+    if !skipped.is_empty() {
+        skipped.sort();
+        println!("\nSkipped ({}):", skipped.len());
+        for (path, reason) in &skipped {
+            println!("  {}: {}", relative_name(path, folder_path), reason);
+        }
+    }
+    
+    if !failures.is_empty() {
+        failures.sort_by(|a, b| a.0.cmp(&b.0));
+        println!("\nFailed ({}):", failures.len());
+        for (path, err) in &failures {
+            println!("  {}: {}", relative_name(err.path().unwrap_or(path), folder_path), err);
+        }
+    }
+    
+    if options.dry_run && failures.is_empty() {
+        println!("\n✓ Dry run complete, no files were changed");
+    } else if options.dry_run {
+        println!("\n✗ Dry run complete, {} of {} files would fail", failures.len(), attempted);
+    } else if failures.is_empty() {
+        println!("\n✓ All done!");
+    } else {
+        println!("\n✗ Done, {} of {} files failed", failures.len(), attempted);
+    }
+    if let Some(journal) = journal {
+        println!("To restore the original files: {} undo {} {}", args[0], folder_path.display(), journal.run_id());
+    }
+    
+    if failures.is_empty() {
+        return;
+    }
+    std::process::exit(if failures.len() == attempted { EXIT_FAILURE } else { EXIT_PARTIAL_FAILURE });
+}
@@ -0,0 +1,81 @@
+// Joining subtitle documents, for episodes released as one multi-episode video
+
+use crate::convert::ConvertOptions;
+use crate::subtitle::{Block, CueMeta, SubtitleDocument, SubtitleFormat};
+
+/// Name of the style defined by an ASS `Style:` line.
+fn style_name(line: &str) -> Option<&str> {
+    let fields = line.strip_prefix("Style:")?;
+    Some(fields.split(',').next()?.trim())
+}
+
+impl SubtitleDocument {
+    /// Appends the cues of `other` after this document's cues, converting
+    /// them to this document's format first. Times are taken as they are, so
+    /// `other` should already be shifted to where it belongs.
+    ///
+    /// Only cues are carried over, plus the ASS styles that this document
+    /// doesn't define yet; SRT counters continue from this document's.
+    pub fn append(&mut self, other: &SubtitleDocument, options: &ConvertOptions) {
+        let other = other.convert(self.format, options);
+        
+        match self.format {
+            SubtitleFormat::Ass => {
+                let styles: Vec<String> = self
+                    .blocks
+                    .iter()
+                    .filter_map(|block| match block {
+                        Block::Raw(line) => style_name(line).map(String::from),
+                        Block::Cue(_) => None,
+                    })
+                    .collect();
+                let new_styles: Vec<Block> = other
+                    .blocks
+                    .iter()
+                    .filter(|block| match block {
+                        Block::Raw(line) => style_name(line).is_some_and(|name| !styles.iter().any(|style| style == name)),
+                        Block::Cue(_) => false,
+                    })
+                    .cloned()
+                    .collect();
+                let last_style = self
+                    .blocks
+                    .iter()
+                    .rposition(|block| matches!(block, Block::Raw(line) if style_name(line).is_some()));
+                if let Some(position) = last_style {
+                    self.blocks.splice(position + 1..position + 1, new_styles);
+                }
+                
+                // Events go after the last existing one, ahead of any trailing blank lines
+                let insert_at = self
+                    .blocks
+                    .iter()
+                    .rposition(|block| matches!(block, Block::Cue(_)))
+                    .map_or(self.blocks.len(), |position| position + 1);
+                let cues: Vec<Block> = other.cues().cloned().map(Block::Cue).collect();
+                self.blocks.splice(insert_at..insert_at, cues);
+            }
+            SubtitleFormat::Srt | SubtitleFormat::Vtt => {
+                let mut trailing = Vec::new();
+                while let Some(Block::Raw(line)) = self.blocks.last()
+                    && line.trim().is_empty()
+                {
+                    trailing.extend(self.blocks.pop());
+                }
+                let mut index = self.cues().count();
+                for cue in other.cues() {
+                    let mut cue = cue.clone();
+                    index += 1;
+                    if let CueMeta::Srt { index: Some(counter), .. } = &mut cue.meta {
+                        *counter = index.to_string();
+                    }
+                    if !self.blocks.is_empty() {
+                        self.blocks.push(Block::Raw(String::new()));
+                    }
+                    self.blocks.push(Block::Cue(cue));
+                }
+                self.blocks.extend(trailing);
+            }
+        }
+    }
+}
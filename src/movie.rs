@@ -0,0 +1,91 @@
+// Subtitle/video matching for movies, which have no episode number to go by
+
+use std::path::{Path, PathBuf};
+
+use crate::episode::folder_distance;
+use crate::release::ReleaseInfo;
+
+/// Lowest title similarity at which a subtitle is taken for a video's.
+const MIN_TITLE_SIMILARITY: f64 = 0.6;
+
+/// Title and year of a movie file name, the title reduced to lowercase letters
+/// and digits so `Spider-Man` and `Spiderman` compare equal.
+fn movie_title(name: &str) -> (String, Option<u32>) {
+    let info = ReleaseInfo::parse(name);
+    let title = info.title.unwrap_or_default().chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect();
+    (title, info.year)
+}
+
+fn bigrams(text: &str) -> Vec<(char, char)> {
+    let chars: Vec<char> = text.chars().collect();
+    chars.windows(2).map(|pair| (pair[0], pair[1])).collect()
+}
+
+/// How alike two titles are, from 0 (nothing in common) to 1 (the same):
+/// the Dice coefficient of their letter pairs. An empty title matches nothing.
+pub fn title_similarity(a: &str, b: &str) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+    let a = bigrams(a);
+    let mut b = bigrams(b);
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let total = a.len() + b.len();
+    let mut shared = 0;
+    for pair in &a {
+        if let Some(position) = b.iter().position(|other| other == pair) {
+            b.swap_remove(position);
+            shared += 1;
+        }
+    }
+    2.0 * shared as f64 / total as f64
+}
+
+/// Similarity of the movie titles in two file names; 0 when both carry a year
+/// and the years differ, so remakes stay apart.
+pub fn movie_similarity(a: &str, b: &str) -> f64 {
+    let (title_a, year_a) = movie_title(a);
+    let (title_b, year_b) = movie_title(b);
+    match (year_a, year_b) {
+        (Some(year_a), Some(year_b)) if year_a != year_b => 0.0,
+        _ => title_similarity(&title_a, &title_b),
+    }
+}
+
+/// Returns the video a movie subtitle belongs to: the one whose title is most
+/// like the subtitle's (the nearer folder breaking ties), or, when no title is
+/// alike enough (`English.srt`, `movie.en.srt`), the video nearest to the
+/// subtitle if it is the only one at that distance. A folder with a single
+/// movie thus always matches.
+pub fn find_movie_video<'a>(videos: &'a [PathBuf], subtitle: &Path) -> Option<&'a PathBuf> {
+    let name = subtitle.file_name()?.to_str()?;
+    let folder = subtitle.parent().unwrap_or(Path::new(""));
+    let mut candidates: Vec<(f64, usize, &PathBuf)> = videos
+        .iter()
+        .map(|video| {
+            let video_name = video.file_name().and_then(|name| name.to_str()).unwrap_or("");
+            let distance = folder_distance(folder, video.parent().unwrap_or(Path::new("")));
+            (movie_similarity(name, video_name), distance, video)
+        })
+        .collect();
+    
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+    if let [(score, distance, video), rest @ ..] = candidates.as_slice()
+        && *score >= MIN_TITLE_SIMILARITY
+    {
+        let tied = rest.first().is_some_and(|(next_score, next_distance, _)| next_score == score && next_distance == distance);
+        return (!tied).then_some(*video);
+    }
+    
+    candidates.sort_by_key(|(_, distance, _)| *distance);
+    match candidates.as_slice() {
+        [(_, _, video)] => Some(video),
+        [(_, nearest, video), (_, next, _), ..] if nearest < next => Some(video),
+        _ => None,
+    }
+}
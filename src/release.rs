@@ -0,0 +1,467 @@
+// Release-name parsing for fansub and scene file names, in the style of anitomy
+
+use std::fmt;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::episode::EpisodeKey;
+use crate::tags::SubtitleTags;
+
+/// Extensions stripped from the end of a name before parsing it.
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "mkv", "mp4", "m4v", "avi", "mov", "wmv", "webm", "ts", "m2ts", "mts", "flv", "ogm", "ogv", "mpg", "mpeg", "vob",
+    "divx", "rmvb", "srt", "ass", "ssa", "vtt", "sub", "idx", "sup", "wav", "flac", "mka",
+];
+
+const VIDEO_TERMS: &[&str] = &[
+    "x264", "x265", "h264", "h265", "hevc", "avc", "xvid", "divx", "av1", "vp9", "10bit", "10-bit", "8bit", "8-bit",
+    "hi10", "hi10p", "hdr", "hdr10", "dv", "sdr", "60fps",
+];
+
+const SOURCES: &[&str] = &[
+    "bluray", "blu-ray", "bd", "bdrip", "brrip", "bdremux", "remux", "bdmv", "web", "web-dl", "webdl", "webrip", "hdtv",
+    "hdtvrip", "tv", "tvrip", "dvd", "dvdrip", "dvd5", "dvd9", "vhs", "ld", "laserdisc",
+];
+
+const KEYWORDS: &[&str] = &[
+    "multi", "multi-sub", "multi-subs", "multisub", "multiple", "subtitle", "subtitles", "subs", "subbed", "dub", "dubbed",
+    "dual", "dual-audio", "audio", "vostfr", "vf", "raw", "repack", "proper", "uncensored", "uncut", "batch", "complete",
+    "amzn", "nf", "cr", "dsnp", "hmax", "atvp", "hidive", "funi", "baha", "b-global", "abema",
+];
+
+/// Audio codecs, with an optional channel layout (`AAC2.0`, `DDP5.1`, `FLAC 2.0`).
+static AUDIO_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?:aac|ddp?|dd\+|e?ac-?3|dts(?:-hd)?(?:-?ma)?|truehd|flac|opus|mp3|vorbis|pcm|lpcm|atmos)(?:\d\.?\d)?$|^\d\.\d(?:ch)?$|^\dch$")
+        .unwrap()
+});
+
+static RESOLUTION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^(?:\d{3,4}[pi]|\d{3,4}x\d{3,4}|[48]k|uhd|fhd)$").unwrap());
+
+static CRC_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[0-9A-Fa-f]{8}$").unwrap());
+
+/// `S01E05`, `S01E01-E02`, `S01E01E02`, `S01E01-02`, with an optional `v2`.
+static SEASON_EPISODE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^s(\d{1,2})[._-]?e(\d{1,4})(?:-?e(\d{1,4})|-(\d{1,4}))?(?:v(\d))?$").unwrap()
+});
+
+/// `1x05`, `1x01-1x02`, `1x01-02`.
+static CROSS_EPISODE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^(\d{1,2})x(\d{2,3})(?:-(?:\d{1,2}x)?(\d{2,3}))?$").unwrap());
+
+/// `E05`, `EP05`, `Episode05`, `#05`, `E01-E02`, `ep01-02`, `E05v2`.
+static PREFIXED_EPISODE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?:e|ep|episode|#)(\d{1,4})(?:-(?:e|ep)?(\d{1,4}))?(?:v(\d))?$").unwrap()
+});
+
+/// `05`, `05v2`, `01-02`, `01~02`.
+static NUMBER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^(\d{1,4})(?:[-~](\d{1,4}))?(?:v(\d))?$").unwrap());
+
+static SEASON_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)^s(\d{1,2})$").unwrap());
+static ORDINAL_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)^(\d{1,2})(?:st|nd|rd|th)$").unwrap());
+static VERSION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)^v(\d)$").unwrap());
+static YEAR_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(?:19|20)\d{2}$").unwrap());
+
+/// Dotted codec and channel names that must survive splitting a name on dots.
+static DOTTED_TERM_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(h|x)\.(26[45])\b|\b(aac|ddp|dd|eac3|ac3|dts|truehd|flac|opus)(\d)\.(\d)\b").unwrap()
+});
+
+/// Which rule found the episode number of a name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EpisodeRule {
+    /// `S01E05`, `S01E01-E02`
+    SeasonEpisode,
+    /// `1x05`
+    Cross,
+    /// `E05`, `EP05`, `#05`
+    Prefixed,
+    /// `Episode 5`, `Ep 05`
+    Keyword,
+    /// `05v2`
+    Versioned,
+    /// `Title - 05`
+    AfterSeparator,
+    /// `Title 05`
+    LastNumber,
+    /// `Title [05]`
+    Bracketed,
+}
+
+impl fmt::Display for EpisodeRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            EpisodeRule::SeasonEpisode => "season and episode",
+            EpisodeRule::Cross => "season x episode",
+            EpisodeRule::Prefixed => "episode prefix",
+            EpisodeRule::Keyword => "episode keyword",
+            EpisodeRule::Versioned => "versioned number",
+            EpisodeRule::AfterSeparator => "number after ' - '",
+            EpisodeRule::LastNumber => "last number in the name",
+            EpisodeRule::Bracketed => "number in brackets",
+        })
+    }
+}
+
+/// What a parsed file name describes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReleaseInfo {
+    pub title: Option<String>,
+    pub release_group: Option<String>,
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+    /// Last episode of a multi-episode file.
+    pub last_episode: Option<u32>,
+    /// Release version, from `01v2` or a separate `v2`.
+    pub version: Option<u32>,
+    pub year: Option<u32>,
+    pub resolution: Option<String>,
+    /// Video codec and encoding terms (`x265`, `HEVC`, `10bit`).
+    pub video_terms: Vec<String>,
+    /// Audio codec terms (`FLAC`, `AAC2.0`).
+    pub audio_terms: Vec<String>,
+    pub source: Option<String>,
+    /// CRC32 checksum tag, as written.
+    pub crc: Option<String>,
+    pub extension: Option<String>,
+    /// How the episode number was found, and the part of the name it was read from.
+    pub episode_rule: Option<(EpisodeRule, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Meta {
+    Resolution,
+    VideoTerm,
+    AudioTerm,
+    Source,
+    Crc,
+    Year,
+    Keyword,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// Title words and anything else not recognized.
+    Unknown,
+    /// A ` - ` between parts of the name.
+    Separator,
+    Group,
+    Meta(Meta),
+    Season(u32),
+    Version(u32),
+    /// A bare number that may be the episode.
+    Number { episode: u32, last: Option<u32>, version: Option<u32> },
+    /// An unambiguous episode marker.
+    Episode { season: Option<u32>, episode: u32, last: Option<u32>, version: Option<u32>, rule: EpisodeRule },
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    /// Inside brackets or parentheses.
+    enclosed: bool,
+    kind: Kind,
+}
+
+fn is_one_of(word: &str, list: &[&str]) -> bool {
+    let lower = word.to_lowercase();
+    list.contains(&lower.as_str())
+}
+
+fn number(caps: &regex::Captures, group: usize) -> Option<u32> {
+    caps.get(group)?.as_str().parse().ok()
+}
+
+/// Classifies one word on its own, without looking at its neighbours.
+fn classify(word: &str) -> Kind {
+    if word == "-" || word == "~" {
+        return Kind::Separator;
+    }
+    if RESOLUTION_RE.is_match(word) {
+        return Kind::Meta(Meta::Resolution);
+    }
+    if is_one_of(word, VIDEO_TERMS) {
+        return Kind::Meta(Meta::VideoTerm);
+    }
+    if AUDIO_RE.is_match(word) {
+        return Kind::Meta(Meta::AudioTerm);
+    }
+    if is_one_of(word, SOURCES) {
+        return Kind::Meta(Meta::Source);
+    }
+    if is_one_of(word, KEYWORDS) {
+        return Kind::Meta(Meta::Keyword);
+    }
+    if YEAR_RE.is_match(word) {
+        return Kind::Meta(Meta::Year);
+    }
+    if let Some(caps) = SEASON_EPISODE_RE.captures(word)
+        && let (Some(season), Some(episode)) = (number(&caps, 1), number(&caps, 2))
+    {
+        let last = number(&caps, 3).or_else(|| number(&caps, 4));
+        let rule = EpisodeRule::SeasonEpisode;
+        return Kind::Episode { season: Some(season), episode, last, version: number(&caps, 5), rule };
+    }
+    if let Some(caps) = CROSS_EPISODE_RE.captures(word)
+        && let (Some(season), Some(episode)) = (number(&caps, 1), number(&caps, 2))
+    {
+        return Kind::Episode { season: Some(season), episode, last: number(&caps, 3), version: None, rule: EpisodeRule::Cross };
+    }
+    if let Some(caps) = PREFIXED_EPISODE_RE.captures(word)
+        && let Some(episode) = number(&caps, 1)
+    {
+        let (last, version) = (number(&caps, 2), number(&caps, 3));
+        return Kind::Episode { season: None, episode, last, version, rule: EpisodeRule::Prefixed };
+    }
+    if let Some(caps) = NUMBER_RE.captures(word)
+        && let Some(episode) = number(&caps, 1)
+    {
+        let (last, version) = (number(&caps, 2), number(&caps, 3));
+        // A version suffix only ever follows an episode number
+        if version.is_some() {
+            return Kind::Episode { season: None, episode, last, version, rule: EpisodeRule::Versioned };
+        }
+        return Kind::Number { episode, last, version };
+    }
+    if let Some(caps) = SEASON_RE.captures(word)
+        && let Some(season) = number(&caps, 1)
+    {
+        return Kind::Season(season);
+    }
+    if let Some(caps) = VERSION_RE.captures(word)
+        && let Some(version) = number(&caps, 1)
+    {
+        return Kind::Version(version);
+    }
+    Kind::Unknown
+}
+
+/// Classifies a run of words, joining `Episode 5`, `Season 2` and `2nd Season`.
+fn classify_words(words: &[&str], enclosed: bool) -> Vec<Token> {
+    let mut tokens: Vec<Token> =
+        words.iter().map(|word| Token { text: word.to_string(), enclosed, kind: classify(word) }).collect();
+    
+    for i in 0..tokens.len().saturating_sub(1) {
+        let lower = tokens[i].text.to_lowercase();
+        let next_lower = tokens[i + 1].text.to_lowercase();
+        match (lower.as_str(), tokens[i + 1].kind) {
+            ("e" | "ep" | "episode" | "#", Kind::Number { episode, last, version }) => {
+                tokens[i].kind = Kind::Meta(Meta::Keyword);
+                tokens[i + 1].kind = Kind::Episode { season: None, episode, last, version, rule: EpisodeRule::Keyword };
+            }
+            ("season" | "series" | "saison" | "staffel", Kind::Number { episode, .. }) => {
+                tokens[i].kind = Kind::Meta(Meta::Keyword);
+                tokens[i + 1].kind = Kind::Season(episode);
+            }
+            _ if ORDINAL_RE.is_match(&lower) && next_lower == "season" => {
+                if let Some(season) = ORDINAL_RE.captures(&lower).and_then(|caps| number(&caps, 1)) {
+                    tokens[i].kind = Kind::Season(season);
+                    tokens[i + 1].kind = Kind::Meta(Meta::Keyword);
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+/// Splits `name` into bracketed groups and the free text between them.
+fn split_brackets(name: &str) -> Vec<(String, bool)> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut closer = None;
+    for c in name.chars() {
+        match closer {
+            None => {
+                let close = match c {
+                    '[' => Some(']'),
+                    '(' => Some(')'),
+                    '{' => Some('}'),
+                    '【' => Some('】'),
+                    '「' => Some('」'),
+                    _ => None,
+                };
+                if close.is_some() {
+                    if !current.trim().is_empty() {
+                        parts.push((std::mem::take(&mut current), false));
+                    }
+                    current.clear();
+                    closer = close;
+                } else {
+                    current.push(c);
+                }
+            }
+            Some(close) if c == close => {
+                parts.push((std::mem::take(&mut current), true));
+                closer = None;
+            }
+            Some(_) => current.push(c),
+        }
+    }
+    if !current.trim().is_empty() {
+        // An unclosed bracket is read as plain text
+        parts.push((current, false));
+    }
+    parts
+}
+
+/// Removes a media extension and trailing language/flag tags.
+fn strip_suffixes(name: &str) -> (&str, Option<String>) {
+    let (mut stem, extension) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && is_one_of(ext, MEDIA_EXTENSIONS) => (stem, Some(ext.to_lowercase())),
+        _ => (name, None),
+    };
+    let tags = SubtitleTags::parse(stem);
+    for _ in 0..tags.language.is_some() as usize + tags.flags.len() {
+        stem = stem.rsplit_once('.').map_or(stem, |(rest, _)| rest);
+    }
+    (stem, extension)
+}
+
+impl ReleaseInfo {
+    /// Parses a file name such as `[Group] Title - 05v2 [1080p][ABCD1234].mkv`
+    /// or `Title.S01E05.1080p.WEB-DL.x264-GROUP.mkv`.
+    pub fn parse(filename: &str) -> Self {
+        let (stem, extension) = strip_suffixes(filename);
+        let mut info = ReleaseInfo { extension, ..ReleaseInfo::default() };
+        
+        let parts = split_brackets(stem);
+        let free_text: String = parts.iter().filter(|(_, enclosed)| !enclosed).map(|(text, _)| text.as_str()).collect();
+        let dot_delimited = !free_text.contains(' ') && !free_text.contains('_');
+        
+        let mut tokens: Vec<Token> = Vec::new();
+        for (text, enclosed) in &parts {
+            if *enclosed {
+                let words: Vec<&str> = text.split([' ', '_', ',']).filter(|word| !word.is_empty()).collect();
+                let classified = classify_words(&words, true);
+                if !classified.is_empty() && classified.iter().all(|token| token.kind != Kind::Unknown) {
+                    tokens.extend(classified);
+                } else if CRC_RE.is_match(text) {
+                    tokens.push(Token { text: text.clone(), enclosed: true, kind: Kind::Meta(Meta::Crc) });
+                } else {
+                    tokens.push(Token { text: text.trim().to_string(), enclosed: true, kind: Kind::Unknown });
+                }
+                continue;
+            }
+            
+            let words: Vec<String> = if dot_delimited {
+                let joined = DOTTED_TERM_RE.replace_all(text, "$1$2$3$4$5");
+                joined.split('.').filter(|word| !word.is_empty()).map(String::from).collect()
+            } else {
+                text.split([' ', '_']).filter(|word| !word.is_empty()).map(String::from).collect()
+            };
+            let words: Vec<&str> = words.iter().map(String::as_str).collect();
+            tokens.extend(classify_words(&words, false));
+        }
+        
+        // A leading bracket is the release group, so is `-GROUP` glued to the last metadata word
+        if let Some(first) = tokens.first_mut()
+            && first.enclosed
+            && first.kind == Kind::Unknown
+        {
+            first.kind = Kind::Group;
+        }
+        if let Some(position) = tokens.iter().rposition(|token| !token.enclosed)
+            && tokens[position].kind == Kind::Unknown
+            && let Some((term, group)) = tokens[position].text.rsplit_once('-')
+            && !group.is_empty()
+            && let Kind::Meta(meta) = classify(term)
+        {
+            let group = group.to_string();
+            tokens[position] = Token { text: term.to_string(), enclosed: false, kind: Kind::Meta(meta) };
+            tokens.insert(position + 1, Token { text: group, enclosed: false, kind: Kind::Group });
+        }
+        
+        for token in &tokens {
+            match token.kind {
+                Kind::Group if info.release_group.is_none() => info.release_group = Some(token.text.clone()),
+                Kind::Meta(Meta::Resolution) if info.resolution.is_none() => info.resolution = Some(token.text.clone()),
+                Kind::Meta(Meta::VideoTerm) => info.video_terms.push(token.text.clone()),
+                Kind::Meta(Meta::AudioTerm) => info.audio_terms.push(token.text.clone()),
+                Kind::Meta(Meta::Source) if info.source.is_none() => info.source = Some(token.text.clone()),
+                Kind::Meta(Meta::Crc) if info.crc.is_none() => info.crc = Some(token.text.clone()),
+                Kind::Meta(Meta::Year) if info.year.is_none() => info.year = token.text.parse().ok(),
+                Kind::Season(season) if info.season.is_none() => info.season = Some(season),
+                Kind::Version(version) if info.version.is_none() => info.version = Some(version),
+                _ => {}
+            }
+        }
+        
+        let found = find_episode(&tokens);
+        if let Some((index, rule)) = found {
+            match tokens[index].kind {
+                Kind::Episode { season, episode, last, version, .. } => {
+                    info.season = season.or(info.season);
+                    info.episode = Some(episode);
+                    info.last_episode = last;
+                    info.version = version.or(info.version);
+                }
+                Kind::Number { episode, last, version } => {
+                    info.episode = Some(episode);
+                    info.last_episode = last;
+                    info.version = version.or(info.version);
+                }
+                _ => {}
+            }
+            info.episode_rule = Some((rule, tokens[index].text.clone()));
+        }
+        info.title = find_title(&tokens, found.map(|(index, _)| index));
+        info
+    }
+    
+    /// The season and episode (or episode range) for matching, if an episode was found.
+    pub fn episode_key(&self) -> Option<EpisodeKey> {
+        Some(EpisodeKey::range(self.season, self.episode?, self.last_episode))
+    }
+}
+
+/// Picks the token holding the episode number: an explicit marker, else a
+/// number right after ` - `, else the last bare number outside brackets,
+/// else a bare number in brackets.
+fn find_episode(tokens: &[Token]) -> Option<(usize, EpisodeRule)> {
+    let is_number = |token: &Token| matches!(token.kind, Kind::Number { .. });
+    tokens
+        .iter()
+        .enumerate()
+        .find_map(|(i, token)| match token.kind {
+            Kind::Episode { rule, .. } => Some((i, rule)),
+            _ => None,
+        })
+        .or_else(|| {
+            tokens
+                .windows(2)
+                .position(|pair| pair[0].kind == Kind::Separator && !pair[1].enclosed && is_number(&pair[1]))
+                .map(|position| (position + 1, EpisodeRule::AfterSeparator))
+        })
+        .or_else(|| {
+            let position = tokens.iter().rposition(|token| !token.enclosed && is_number(token))?;
+            Some((position, EpisodeRule::LastNumber))
+        })
+        .or_else(|| {
+            let position = tokens.iter().position(|token| token.enclosed && is_number(token))?;
+            Some((position, EpisodeRule::Bracketed))
+        })
+}
+
+/// The free words from the start of the name (after any leading brackets)
+/// up to the episode or the first recognized term.
+fn find_title(tokens: &[Token], episode_index: Option<usize>) -> Option<String> {
+    let start = tokens.iter().position(|token| !token.enclosed)?;
+    let mut words: Vec<&str> = Vec::new();
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        let title_word = matches!(token.kind, Kind::Unknown | Kind::Separator | Kind::Number { .. });
+        if Some(i) == episode_index || token.enclosed || !title_word {
+            break;
+        }
+        words.push(&token.text);
+    }
+    while words.last().is_some_and(|word| *word == "-" || *word == "~") {
+        words.pop();
+    }
+    while words.first().is_some_and(|word| *word == "-" || *word == "~") {
+        words.remove(0);
+    }
+    (!words.is_empty()).then(|| words.join(" "))
+}
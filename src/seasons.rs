@@ -0,0 +1,130 @@
+// Translation between absolute (`- 113`) and seasonal (`S05E13`) episode numbering
+
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::episode::EpisodeKey;
+
+/// One line of a mapping file: `S05 101-124`, `5: 101-124` or `Season 5 = 101-124`.
+/// A bare number needs the `:` or `=`, so `105-124` is not read as season 10.
+static MAPPING_LINE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?:(?:season\s*|s)(\d{1,3})\s*[:=]?|(\d{1,3})\s*[:=])\s*(\d{1,5})\s*-\s*(\d{1,5})$").unwrap()
+});
+
+/// Absolute episode numbers covered by one season.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SeasonRange {
+    season: u32,
+    first: u32,
+    last: u32,
+}
+
+/// How the absolute episode numbers of a series split into seasons.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SeasonMap {
+    seasons: Vec<SeasonRange>,
+}
+
+impl SeasonMap {
+    /// Seasons 1, 2, ... with the given number of episodes each, numbered on from absolute episode 1.
+    pub fn from_lengths(lengths: &[u32]) -> Result<SeasonMap, String> {
+        let mut map = SeasonMap::default();
+        let mut first: u32 = 1;
+        for (index, &length) in lengths.iter().enumerate() {
+            if length == 0 {
+                return Err(format!("season {} has no episodes", index + 1));
+            }
+            let last = first
+                .checked_add(length - 1)
+                .ok_or_else(|| format!("season {} goes past episode {}", index + 1, u32::MAX))?;
+            map.add(index as u32 + 1, first, last)?;
+            first = last.saturating_add(1);
+        }
+        Ok(map)
+    }
+    
+    /// Parses a comma-separated list of season lengths, e.g. `25,25,24`.
+    pub fn parse_lengths(value: &str) -> Result<SeasonMap, String> {
+        let lengths = value
+            .split(',')
+            .map(|length| length.trim().parse::<u32>().map_err(|_| format!("invalid season length '{}'", length.trim())))
+            .collect::<Result<Vec<_>, _>>()?;
+        SeasonMap::from_lengths(&lengths)
+    }
+    
+    /// Parses a mapping file with one `<season> <first>-<last>` line per season.
+    /// Blank lines and lines starting with `#` are ignored.
+    pub fn parse(content: &str) -> Result<SeasonMap, String> {
+        let mut map = SeasonMap::default();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let caps = MAPPING_LINE_RE
+                .captures(line)
+                .ok_or_else(|| format!("line {}: expected '<season> <first>-<last>', found '{}'", number + 1, line))?;
+            let season = caps.get(1).or(caps.get(2)).map_or("", |m| m.as_str());
+            let [season, first, last] = [season, &caps[3], &caps[4]].map(|value| value.parse::<u32>().unwrap_or(0));
+            map.add(season, first, last).map_err(|message| format!("line {}: {}", number + 1, message))?;
+        }
+        if map.seasons.is_empty() {
+            return Err("no seasons given".to_string());
+        }
+        Ok(map)
+    }
+    
+    /// Reads a mapping file, see [`SeasonMap::parse`].
+    pub fn load(path: &Path) -> Result<SeasonMap, String> {
+        let content = fs::read_to_string(path).map_err(|err| format!("cannot read '{}': {}", path.display(), err))?;
+        SeasonMap::parse(&content).map_err(|message| format!("{}: {}", path.display(), message))
+    }
+    
+    fn add(&mut self, season: u32, first: u32, last: u32) -> Result<(), String> {
+        if first == 0 || last < first {
+            return Err(format!("invalid episode range {}-{}", first, last));
+        }
+        if self.seasons.iter().any(|range| range.season == season) {
+            return Err(format!("season {} is given twice", season));
+        }
+        if let Some(other) = self.seasons.iter().find(|range| first <= range.last && range.first <= last) {
+            return Err(format!("episodes {}-{} overlap season {}", first, last, other.season));
+        }
+        self.seasons.push(SeasonRange { season, first, last });
+        Ok(())
+    }
+    
+    /// Season and episode within it of an absolute episode number.
+    pub fn to_seasonal(&self, absolute: u32) -> Option<(u32, u32)> {
+        let range = self.seasons.iter().find(|range| (range.first..=range.last).contains(&absolute))?;
+        Some((range.season, absolute - range.first + 1))
+    }
+    
+    /// Absolute number of an episode of a season.
+    pub fn to_absolute(&self, season: u32, episode: u32) -> Option<u32> {
+        let range = self.seasons.iter().find(|range| range.season == season)?;
+        let absolute = range.first + episode.checked_sub(1)?;
+        (absolute <= range.last).then_some(absolute)
+    }
+    
+    /// The same episode(s) in the other numbering: a key without a season is
+    /// read as absolute and given its season, a seasonal key loses its season.
+    /// `None` if the map doesn't cover it, or a range would span two seasons.
+    pub fn translate(&self, key: EpisodeKey) -> Option<EpisodeKey> {
+        match key.season {
+            None => {
+                let (season, episode) = self.to_seasonal(key.episode)?;
+                let (last_season, last_episode) = self.to_seasonal(key.last_episode)?;
+                (season == last_season).then_some(EpisodeKey { season: Some(season), episode, last_episode })
+            }
+            Some(season) => Some(EpisodeKey {
+                season: None,
+                episode: self.to_absolute(season, key.episode)?,
+                last_episode: self.to_absolute(season, key.last_episode)?,
+            }),
+        }
+    }
+}
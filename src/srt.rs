@@ -0,0 +1,193 @@
+// SubRip (.srt) parsing and serialization
+
+use std::fmt;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::subtitle::{Block, Cue, CueMeta, SrtCoordinates, SubtitleDocument, SubtitleFormat, TextLayout};
+use crate::timestamp::{format_timestamp_srt, is_timestamp_srt, parse_timestamp_srt};
+
+/// Splits a timing line at its arrow, which may have lost its spaces or a dash.
+fn split_arrow(line: &str) -> Option<(&str, &str)> {
+    line.split_once("-->").or_else(|| line.split_once("->"))
+}
+
+/// `X1:100 X2:500 Y1:400 Y2:450`, as written after the end time.
+static COORDINATES_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^X1\s*:\s*(-?\d+)\s+X2\s*:\s*(-?\d+)\s+Y1\s*:\s*(-?\d+)\s+Y2\s*:\s*(-?\d+)$").unwrap()
+});
+
+fn parse_coordinates(text: &str) -> Option<SrtCoordinates> {
+    let caps = COORDINATES_RE.captures(text)?;
+    let [x1, x2, y1, y2] = [&caps[1], &caps[2], &caps[3], &caps[4]].map(|value| value.parse::<i32>().ok());
+    Some(SrtCoordinates { x1: x1?, x2: x2?, y1: y1?, y2: y2? })
+}
+
+/// Reads a timing line: start, end and the coordinates that may follow the end.
+fn parse_timing_line(line: &str) -> Option<(i64, i64, Option<SrtCoordinates>)> {
+    let (start, rest) = split_arrow(line)?;
+    let rest = rest.trim();
+    let (end, coordinates) = match rest.split_once(char::is_whitespace) {
+        Some((end, coordinates)) => (end, Some(parse_coordinates(coordinates.trim())?)),
+        None => (rest, None),
+    };
+    Some((parse_timestamp_srt(start.trim())?, parse_timestamp_srt(end)?, coordinates))
+}
+
+/// Whether a line is meant as a timing line, readable or not: an arrow after
+/// a timestamp. Text such as `He said --> go` is not one.
+fn looks_like_timing(line: &str) -> bool {
+    split_arrow(line).is_some_and(|(start, _)| is_timestamp_srt(start.trim()))
+}
+
+fn is_index_line(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && line.chars().all(|c| c.is_ascii_digit())
+}
+
+/// What was wrong with a line of an SRT file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineIssue {
+    /// A timing line in a non-standard form (`00:00:01.500`, `0:00:01,5`,
+    /// `-->` without spaces, ...); it was read and is written normalized.
+    Repaired,
+    /// A timing line that could not be read; it is kept as it was and its cue isn't shifted.
+    InvalidTiming,
+    /// A line outside of any cue; it is kept as it was.
+    Stray,
+}
+
+/// A line of an SRT file that needed repair or could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    /// Line number, from 1.
+    pub line: usize,
+    pub issue: LineIssue,
+    /// The line as it was in the file.
+    pub text: String,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = self.text.trim_end();
+        match self.issue {
+            LineIssue::Repaired => write!(f, "line {}: repaired timing '{}'", self.line, text),
+            LineIssue::InvalidTiming => write!(f, "line {}: unreadable timing '{}', left unshifted", self.line, text),
+            LineIssue::Stray => write!(f, "line {}: '{}' is not part of a cue, kept as is", self.line, text),
+        }
+    }
+}
+
+/// Parses SRT content into a [`SubtitleDocument`].
+pub fn parse(content: &str) -> SubtitleDocument {
+    parse_with_warnings(content).0
+}
+
+/// Parses SRT content into a [`SubtitleDocument`], tolerating the malformed
+/// timing lines real files have, and lists every line that had to be repaired
+/// or could not be parsed.
+pub fn parse_with_warnings(content: &str) -> (SubtitleDocument, Vec<ParseWarning>) {
+    let (layout, lines) = TextLayout::split(content);
+    let mut blocks = Vec::new();
+    let mut warnings: Vec<ParseWarning> = Vec::new();
+    // In the counter and text of a cue whose timing could not be read, which
+    // are left out of the warnings as the timing line already stands for them
+    let mut in_invalid_cue = false;
+    let mut i = 0;
+    
+    while i < lines.len() {
+        let Some((start, end, coordinates)) = parse_timing_line(lines[i]) else {
+            let line = lines[i];
+            if looks_like_timing(line) {
+                if i > 0 && is_index_line(lines[i - 1]) && warnings.last().is_some_and(|warning| warning.line == i) {
+                    warnings.pop();
+                }
+                warnings.push(ParseWarning { line: i + 1, issue: LineIssue::InvalidTiming, text: line.to_string() });
+                in_invalid_cue = true;
+            } else if line.trim().is_empty() {
+                in_invalid_cue = false;
+            } else if !in_invalid_cue {
+                warnings.push(ParseWarning { line: i + 1, issue: LineIssue::Stray, text: line.to_string() });
+            }
+            blocks.push(Block::Raw(line.to_string()));
+            i += 1;
+            continue;
+        };
+        in_invalid_cue = false;
+        
+        // The counter line directly above the timing belongs to the cue, and is
+        // no stray line; inside an unreadable cue it had no warning of its own
+        let index = match blocks.last() {
+            Some(Block::Raw(line)) if is_index_line(line) => match blocks.pop() {
+                Some(Block::Raw(line)) => {
+                    if warnings.last().is_some_and(|warning| warning.line == i && warning.issue == LineIssue::Stray) {
+                        warnings.pop();
+                    }
+                    Some(line)
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        
+        let (timing, timing_number) = (lines[i], i + 1);
+        i += 1;
+        let mut text_lines = Vec::new();
+        while i < lines.len() && !lines[i].trim().is_empty() && !looks_like_timing(lines[i]) {
+            text_lines.push(lines[i]);
+            i += 1;
+        }
+        
+        let meta = CueMeta::Srt { index, coordinates };
+        let mut cue = Cue { start, end, text: text_lines.join("\n"), meta, source_line: None };
+        // Only trailing whitespace is kept as written; anything else is normalized
+        let normalized = timing_line(&cue);
+        if timing.trim_end() == normalized {
+            cue.keep_source_line(normalized, timing);
+        } else {
+            warnings.push(ParseWarning { line: timing_number, issue: LineIssue::Repaired, text: timing.to_string() });
+        }
+        blocks.push(Block::Cue(cue));
+    }
+    
+    (SubtitleDocument { format: SubtitleFormat::Srt, blocks, layout }, warnings)
+}
+
+fn timing_line(cue: &Cue) -> String {
+    let mut line = format!("{} --> {}", format_timestamp_srt(cue.start), format_timestamp_srt(cue.end));
+    if let CueMeta::Srt { coordinates: Some(coordinates), .. } = &cue.meta {
+        line.push_str(&format!(" X1:{} X2:{} Y1:{} Y2:{}", coordinates.x1, coordinates.x2, coordinates.y1, coordinates.y2));
+    }
+    line
+}
+
+/// Serializes a document as SRT.
+pub fn serialize(doc: &SubtitleDocument) -> String {
+    let mut result = String::new();
+    
+    for block in &doc.blocks {
+        match block {
+            Block::Raw(line) => {
+                result.push_str(line);
+                result.push('\n');
+            }
+            Block::Cue(cue) => {
+                if let CueMeta::Srt { index: Some(index), .. } = &cue.meta {
+                    result.push_str(index);
+                    result.push('\n');
+                }
+                result.push_str(&cue.source_or(timing_line(cue)));
+                result.push('\n');
+                if !cue.text.is_empty() {
+                    for line in cue.text.split('\n') {
+                        result.push_str(line);
+                        result.push('\n');
+                    }
+                }
+            }
+        }
+    }
+    
+    doc.layout.apply(result)
+}
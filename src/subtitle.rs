@@ -0,0 +1,245 @@
+// Format-independent subtitle document model
+
+use crate::timing::LinearMap;
+use crate::{ass, srt, vtt};
+
+/// A subtitle file format supported by SubSync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubtitleFormat {
+    Srt,
+    Ass,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    /// Looks up a format from a file extension (case-insensitive, without the dot).
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "srt" => Some(SubtitleFormat::Srt),
+            "ass" => Some(SubtitleFormat::Ass),
+            "vtt" => Some(SubtitleFormat::Vtt),
+            _ => None,
+        }
+    }
+    
+    /// The canonical file extension for this format, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Ass => "ass",
+            SubtitleFormat::Vtt => "vtt",
+        }
+    }
+}
+
+/// Fields of an ASS `Dialogue:` event other than its timing and text.
+///
+/// Values are kept exactly as written so untouched events serialize unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssEvent {
+    pub layer: String,
+    pub style: String,
+    pub name: String,
+    pub margin_l: String,
+    pub margin_r: String,
+    pub margin_v: String,
+    pub effect: String,
+}
+
+/// The box an SRT cue is shown in, from the `X1:100 X2:500 Y1:400 Y2:450`
+/// some files put after the timing, in pixels of the video.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SrtCoordinates {
+    pub x1: i32,
+    pub x2: i32,
+    pub y1: i32,
+    pub y2: i32,
+}
+
+/// Format-specific data attached to a cue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CueMeta {
+    /// SRT cue with its counter line and the coordinates after its timing, if present.
+    Srt { index: Option<String>, coordinates: Option<SrtCoordinates> },
+    /// ASS dialogue event.
+    Ass(AssEvent),
+    /// WebVTT cue with its optional identifier and the settings after the end time
+    /// (`line:`, `position:`, ...), kept as written.
+    Vtt { identifier: Option<String>, settings: String },
+}
+
+/// A single timed subtitle entry. Times are in milliseconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cue {
+    pub start: i64,
+    pub end: i64,
+    /// Cue text in the format's own markup, lines separated by `\n`.
+    pub text: String,
+    pub meta: CueMeta,
+    /// The line the cue was read from, if the file wrote it differently from us.
+    pub source_line: Option<Box<SourceLine>>,
+}
+
+/// A cue's timing line (or ASS `Dialogue:` line) as it was read from a file
+/// that formats it differently than serializing does (ASS `0:00:02.5`, WebVTT
+/// `00:01.000`, extra spaces). It is written back as it was while the cue is
+/// unchanged. SRT timing lines that had to be repaired (`0:00:01,5`,
+/// `-->` without spaces, ...) are not kept: they are always written normalized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLine {
+    /// The line as serializing writes it for the cue as it was read.
+    pub normalized: String,
+    /// The line as it was in the file.
+    pub original: String,
+}
+
+impl Cue {
+    /// Remembers `original` as the line this cue was read from, if it differs
+    /// from `normalized`, the line serializing writes for the cue.
+    pub(crate) fn keep_source_line(&mut self, normalized: String, original: &str) {
+        if normalized != original {
+            self.source_line = Some(Box::new(SourceLine { normalized, original: original.to_string() }));
+        }
+    }
+    
+    /// The line to write for this cue, given the line serializing produced for
+    /// it: the original line if the cue still produces what it did when read.
+    pub(crate) fn source_or(&self, line: String) -> String {
+        match &self.source_line {
+            Some(source) if source.normalized == line => source.original.clone(),
+            _ => line,
+        }
+    }
+}
+
+/// A piece of a subtitle document: either a cue or a line kept verbatim.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    Cue(Cue),
+    Raw(String),
+}
+
+/// How the lines of a subtitle file are laid out, so serializing writes them
+/// back the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextLayout {
+    /// The text starts with a byte order mark (U+FEFF).
+    pub bom: bool,
+    /// Every line ends with `\r\n`. Otherwise lines end with `\n` and a `\r`
+    /// before it stays part of the line, so files mixing both come back unchanged.
+    pub crlf: bool,
+    /// The last line is followed by a line ending.
+    pub final_newline: bool,
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        TextLayout { bom: false, crlf: false, final_newline: true }
+    }
+}
+
+impl TextLayout {
+    /// Splits `content` into lines, along with the layout to write them back in.
+    pub fn split(content: &str) -> (TextLayout, Vec<&str>) {
+        let (bom, content) = match content.strip_prefix('\u{FEFF}') {
+            Some(rest) => (true, rest),
+            None => (false, content),
+        };
+        let final_newline = content.ends_with('\n');
+        let newlines = content.matches('\n').count();
+        let crlf = newlines > 0 && content.matches("\r\n").count() == newlines;
+        let layout = TextLayout { bom, crlf, final_newline };
+        if content.is_empty() {
+            return (layout, Vec::new());
+        }
+        
+        let mut lines: Vec<&str> = content.strip_suffix('\n').unwrap_or(content).split('\n').collect();
+        if crlf {
+            // An unterminated last line keeps its `\r`
+            let terminated = if final_newline { lines.len() } else { lines.len() - 1 };
+            for line in &mut lines[..terminated] {
+                *line = line.strip_suffix('\r').unwrap_or(line);
+            }
+        }
+        (layout, lines)
+    }
+    
+    /// Lays out serialized text, written as `\n`-terminated lines, the way this layout says.
+    pub fn apply(&self, mut text: String) -> String {
+        if !self.final_newline && text.ends_with('\n') {
+            text.pop();
+        }
+        if self.crlf {
+            text = text.replace('\n', "\r\n");
+        }
+        if self.bom {
+            text.insert(0, '\u{FEFF}');
+        }
+        text
+    }
+}
+
+/// A parsed subtitle file.
+///
+/// Everything that isn't a cue (headers, styles, blank separators, lines that
+/// could not be parsed) is kept as [`Block::Raw`], and the line endings, byte
+/// order mark and cue lines as written are remembered, so serializing a document
+/// whose cues are unchanged reproduces the input byte for byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubtitleDocument {
+    pub format: SubtitleFormat,
+    pub blocks: Vec<Block>,
+    pub layout: TextLayout,
+}
+
+impl SubtitleDocument {
+    /// Parses `content` as the given format.
+    pub fn parse(content: &str, format: SubtitleFormat) -> Self {
+        match format {
+            SubtitleFormat::Srt => srt::parse(content),
+            SubtitleFormat::Ass => ass::parse(content),
+            SubtitleFormat::Vtt => vtt::parse(content),
+        }
+    }
+    
+    /// Writes the document back out in its format.
+    pub fn serialize(&self) -> String {
+        match self.format {
+            SubtitleFormat::Srt => srt::serialize(self),
+            SubtitleFormat::Ass => ass::serialize(self),
+            SubtitleFormat::Vtt => vtt::serialize(self),
+        }
+    }
+    
+    pub fn cues(&self) -> impl Iterator<Item = &Cue> {
+        self.blocks.iter().filter_map(|block| match block {
+            Block::Cue(cue) => Some(cue),
+            Block::Raw(_) => None,
+        })
+    }
+    
+    pub fn cues_mut(&mut self) -> impl Iterator<Item = &mut Cue> {
+        self.blocks.iter_mut().filter_map(|block| match block {
+            Block::Cue(cue) => Some(cue),
+            Block::Raw(_) => None,
+        })
+    }
+    
+    /// Applies `f` to every cue start and end time, clamping negative results to zero.
+    pub fn map_times(&mut self, f: impl Fn(i64) -> i64) {
+        for cue in self.cues_mut() {
+            cue.start = f(cue.start).max(0);
+            cue.end = f(cue.end).max(0);
+        }
+    }
+    
+    /// Shifts every cue by `shift_ms`, clamping negative times to zero.
+    pub fn shift(&mut self, shift_ms: i64) {
+        self.map_times(|ms| ms.saturating_add(shift_ms));
+    }
+    
+    /// Scales every cue time by `map.scale` and then offsets it, see [`LinearMap`].
+    pub fn apply_linear(&mut self, map: &LinearMap) {
+        self.map_times(|ms| map.apply(ms));
+    }
+}
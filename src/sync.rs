@@ -0,0 +1,173 @@
+// Synchronization from known (old, new) anchor points
+
+use crate::subtitle::SubtitleDocument;
+use crate::timestamp::{format_timestamp_srt, parse_timestamp_any};
+use crate::timing::{LinearMap, Ratio};
+
+/// Where an anchor sits in the unsynchronized subtitle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnchorPoint {
+    /// Start of the n-th cue (1-based, like SRT counters).
+    Cue(usize),
+    /// A timestamp in milliseconds.
+    Time(i64),
+}
+
+/// A known correspondence: `old` in the subtitle should end up at `new` milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Anchor {
+    pub old: AnchorPoint,
+    pub new: i64,
+}
+
+impl Anchor {
+    /// Parses `OLD=NEW`, where `OLD` is a timestamp or `#N` for the N-th cue
+    /// and `NEW` a timestamp, e.g. `00:01:02,500=00:01:04,100` or `#12=0:01:04.10`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (old, new) = s.split_once('=')?;
+        let old = match old.trim().strip_prefix('#') {
+            Some(index) => AnchorPoint::Cue(index.parse().ok().filter(|&i| i > 0)?),
+            None => AnchorPoint::Time(parse_timestamp_any(old)?),
+        };
+        Some(Anchor { old, new: parse_timestamp_any(new)? })
+    }
+}
+
+impl AnchorPoint {
+    /// Resolves the anchor to a time in `doc`.
+    pub fn resolve(self, doc: &SubtitleDocument) -> Result<i64, String> {
+        match self {
+            AnchorPoint::Time(ms) => Ok(ms),
+            AnchorPoint::Cue(index) => index
+                .checked_sub(1)
+                .and_then(|i| doc.cues().nth(i))
+                .map(|cue| cue.start)
+                .ok_or_else(|| format!("cue #{} does not exist (file has {} cues)", index, doc.cues().count())),
+        }
+    }
+}
+
+/// Builds the linear map that sends `a.0` to `a.1` and `b.0` to `b.1` exactly.
+pub fn two_point_map(a: (i64, i64), b: (i64, i64)) -> Result<LinearMap, String> {
+    let ((old_a, new_a), (old_b, new_b)) = if a.0 <= b.0 { (a, b) } else { (b, a) };
+    if old_a == old_b {
+        return Err(format!("both anchors are at {}", format_timestamp_srt(old_a)));
+    }
+    if new_b <= new_a {
+        return Err("anchors must keep their order after syncing".to_string());
+    }
+    let scale = Ratio::new(new_b - new_a, old_b - old_a).ok_or("invalid anchors")?;
+    Ok(LinearMap { origin: old_a, target: new_a, scale })
+}
+
+/// How times between anchors are mapped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnchorMode {
+    /// Each anchor's offset applies unchanged until the next anchor, for
+    /// releases that differ by cuts (e.g. commercial breaks).
+    Step,
+    /// Times are mapped linearly between neighbouring anchors; outside the
+    /// anchors the first/last segment is extended.
+    Interpolate,
+}
+
+/// A piecewise time mapping through any number of resolved (old, new) anchor points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiecewiseMap {
+    points: Vec<(i64, i64)>,
+    mode: AnchorMode,
+}
+
+impl PiecewiseMap {
+    pub fn new(mut points: Vec<(i64, i64)>, mode: AnchorMode) -> Result<Self, String> {
+        if points.is_empty() {
+            return Err("no anchors given".to_string());
+        }
+        points.sort();
+        for pair in points.windows(2) {
+            let ((old_a, new_a), (old_b, new_b)) = (pair[0], pair[1]);
+            if old_a == old_b {
+                return Err(format!("two anchors are at {}", format_timestamp_srt(old_a)));
+            }
+            if mode == AnchorMode::Interpolate && new_b <= new_a {
+                return Err("anchors must keep their order after syncing".to_string());
+            }
+        }
+        Ok(PiecewiseMap { points, mode })
+    }
+    
+    /// The resolved anchor points, sorted by their old time.
+    pub fn points(&self) -> &[(i64, i64)] {
+        &self.points
+    }
+    
+    pub fn mode(&self) -> AnchorMode {
+        self.mode
+    }
+    
+    /// Index of the last anchor at or before `ms`, or the first anchor if `ms` precedes them all.
+    fn segment(&self, ms: i64) -> usize {
+        self.points.partition_point(|&(old, _)| old <= ms).saturating_sub(1)
+    }
+    
+    /// Offset in effect at `ms` in step mode.
+    pub fn step_offset(&self, ms: i64) -> i64 {
+        let (old, new) = self.points[self.segment(ms)];
+        new - old
+    }
+    
+    pub fn apply(&self, ms: i64) -> i64 {
+        match self.mode {
+            AnchorMode::Step => ms + self.step_offset(ms),
+            AnchorMode::Interpolate if self.points.len() == 1 => ms + self.step_offset(ms),
+            AnchorMode::Interpolate => {
+                let i = self.segment(ms).min(self.points.len() - 2);
+                match two_point_map(self.points[i], self.points[i + 1]) {
+                    Ok(map) => map.apply(ms),
+                    Err(_) => ms + self.step_offset(ms),
+                }
+            }
+        }
+    }
+}
+
+impl SubtitleDocument {
+    /// Resolves anchors against this document into (old, new) times.
+    pub fn resolve_anchors(&self, anchors: &[Anchor]) -> Result<Vec<(i64, i64)>, String> {
+        anchors
+            .iter()
+            .map(|anchor| Ok((anchor.old.resolve(self)?, anchor.new)))
+            .collect()
+    }
+    
+    /// Builds a piecewise map through all `anchors`.
+    pub fn anchor_sync(&self, anchors: &[Anchor], mode: AnchorMode) -> Result<PiecewiseMap, String> {
+        PiecewiseMap::new(self.resolve_anchors(anchors)?, mode)
+    }
+    
+    /// Retimes every cue through `map`.
+    ///
+    /// In step mode a cue keeps its duration: its end moves by the offset in
+    /// effect at its start, so cues spanning a cut are not torn apart.
+    pub fn apply_piecewise(&mut self, map: &PiecewiseMap) {
+        for cue in self.cues_mut() {
+            let (start, end) = match map.mode() {
+                AnchorMode::Step => {
+                    let offset = map.step_offset(cue.start);
+                    (cue.start + offset, cue.end + offset)
+                }
+                AnchorMode::Interpolate => (map.apply(cue.start), map.apply(cue.end)),
+            };
+            cue.start = start.max(0);
+            cue.end = end.max(0);
+        }
+    }
+    
+    /// Computes the scale and offset that move the two anchors to their new times.
+    pub fn two_point_sync(&self, first: Anchor, second: Anchor) -> Result<LinearMap, String> {
+        two_point_map(
+            (first.old.resolve(self)?, first.new),
+            (second.old.resolve(self)?, second.new),
+        )
+    }
+}
@@ -0,0 +1,240 @@
+// Language and flag tags in subtitle file names (e.g. `.pt-BR.forced`)
+
+use std::fmt;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// Known languages as (ISO 639-1, ISO 639-2/B, ISO 639-2/T, English name).
+const LANGUAGES: &[(&str, &str, &str, &str)] = &[
+    ("ar", "ara", "ara", "arabic"),
+    ("bg", "bul", "bul", "bulgarian"),
+    ("bn", "ben", "ben", "bengali"),
+    ("ca", "cat", "cat", "catalan"),
+    ("cs", "cze", "ces", "czech"),
+    ("da", "dan", "dan", "danish"),
+    ("de", "ger", "deu", "german"),
+    ("el", "gre", "ell", "greek"),
+    ("en", "eng", "eng", "english"),
+    ("es", "spa", "spa", "spanish"),
+    ("et", "est", "est", "estonian"),
+    ("eu", "baq", "eus", "basque"),
+    ("fa", "per", "fas", "persian"),
+    ("fi", "fin", "fin", "finnish"),
+    ("fr", "fre", "fra", "french"),
+    ("gl", "glg", "glg", "galician"),
+    ("he", "heb", "heb", "hebrew"),
+    ("hi", "hin", "hin", "hindi"),
+    ("hr", "hrv", "hrv", "croatian"),
+    ("hu", "hun", "hun", "hungarian"),
+    ("id", "ind", "ind", "indonesian"),
+    ("is", "ice", "isl", "icelandic"),
+    ("it", "ita", "ita", "italian"),
+    ("ja", "jpn", "jpn", "japanese"),
+    ("ko", "kor", "kor", "korean"),
+    ("lt", "lit", "lit", "lithuanian"),
+    ("lv", "lav", "lav", "latvian"),
+    ("ms", "may", "msa", "malay"),
+    ("nb", "nob", "nob", "bokmal"),
+    ("nl", "dut", "nld", "dutch"),
+    ("no", "nor", "nor", "norwegian"),
+    ("pl", "pol", "pol", "polish"),
+    ("pt", "por", "por", "portuguese"),
+    ("ro", "rum", "ron", "romanian"),
+    ("ru", "rus", "rus", "russian"),
+    ("sk", "slo", "slk", "slovak"),
+    ("sl", "slv", "slv", "slovenian"),
+    ("sr", "srp", "srp", "serbian"),
+    ("sv", "swe", "swe", "swedish"),
+    ("ta", "tam", "tam", "tamil"),
+    ("te", "tel", "tel", "telugu"),
+    ("th", "tha", "tha", "thai"),
+    ("tl", "tgl", "tgl", "tagalog"),
+    ("tr", "tur", "tur", "turkish"),
+    ("uk", "ukr", "ukr", "ukrainian"),
+    ("ur", "urd", "urd", "urdu"),
+    ("vi", "vie", "vie", "vietnamese"),
+    ("zh", "chi", "zho", "chinese"),
+];
+
+/// A base code with an optional region (`BR`), numeric region (`419`) or script (`Hans`).
+static LANGUAGE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^([a-z]{2,3})(?:[-_]([a-z]{2}|\d{3}|[a-z]{4}))?$").unwrap());
+
+/// How language codes are written in new file names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LanguageStyle {
+    /// As found in the original file name.
+    Keep,
+    /// ISO 639-1 (`ja`, `pt-BR`).
+    Alpha2,
+    /// ISO 639-2/B (`jpn`, `por-BR`).
+    Alpha3,
+}
+
+/// A language tag found in a file name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Language {
+    /// The tag exactly as written.
+    pub original: String,
+    pub alpha2: &'static str,
+    pub alpha3: &'static str,
+    /// Region or script subtag, normalized (`BR`, `419`, `Hans`).
+    pub region: Option<String>,
+}
+
+impl Language {
+    /// Recognizes `ja`, `jpn`, `japanese`, `pt-BR`, `pt_br`, `zh-Hans`, ...
+    pub fn parse(tag: &str) -> Option<Self> {
+        let lower = tag.to_lowercase();
+        if let Some(&(alpha2, alpha3, ..)) = LANGUAGES.iter().find(|(.., name)| *name == lower) {
+            return Some(Language { original: tag.to_string(), alpha2, alpha3, region: None });
+        }
+        
+        let caps = LANGUAGE_RE.captures(tag)?;
+        let base = caps[1].to_lowercase();
+        let &(alpha2, alpha3, ..) = LANGUAGES.iter().find(|(a2, b, t, _)| [*a2, *b, *t].contains(&base.as_str()))?;
+        let region = caps.get(2).map(|region| {
+            let mut chars = region.as_str().chars();
+            match (region.len(), chars.next()) {
+                (4, Some(first)) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+                _ => region.as_str().to_uppercase(),
+            }
+        });
+        Some(Language { original: tag.to_string(), alpha2, alpha3, region })
+    }
+    
+    /// The code written in `style`.
+    pub fn code(&self, style: LanguageStyle) -> String {
+        let base = match style {
+            LanguageStyle::Keep => return self.original.clone(),
+            LanguageStyle::Alpha2 => self.alpha2,
+            LanguageStyle::Alpha3 => self.alpha3,
+        };
+        match &self.region {
+            Some(region) => format!("{}-{}", base, region),
+            None => base.to_string(),
+        }
+    }
+}
+
+/// Track flags understood by Plex, Jellyfin, Kodi and mpv.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFlag {
+    Default,
+    Forced,
+    Sdh,
+    Cc,
+}
+
+impl SubtitleFlag {
+    pub fn parse(tag: &str) -> Option<Self> {
+        match tag.to_lowercase().as_str() {
+            "default" => Some(SubtitleFlag::Default),
+            "forced" => Some(SubtitleFlag::Forced),
+            "sdh" => Some(SubtitleFlag::Sdh),
+            "cc" => Some(SubtitleFlag::Cc),
+            _ => None,
+        }
+    }
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubtitleFlag::Default => "default",
+            SubtitleFlag::Forced => "forced",
+            SubtitleFlag::Sdh => "sdh",
+            SubtitleFlag::Cc => "cc",
+        }
+    }
+}
+
+impl fmt::Display for SubtitleFlag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Two letters not all lowercase: `It`, `NO` could be a language code or a word.
+fn may_be_a_word(segment: &str) -> bool {
+    segment.len() == 2 && !segment.chars().all(|c| c.is_ascii_lowercase())
+}
+
+/// A segment that ends the title: a flag, or release or episode information with a number in it.
+fn is_boundary(segment: &str) -> bool {
+    SubtitleFlag::parse(segment).is_some() || segment.contains(|c: char| c.is_ascii_digit())
+}
+
+/// The language and flags at the end of a subtitle file name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubtitleTags {
+    pub language: Option<Language>,
+    /// In file name order.
+    pub flags: Vec<SubtitleFlag>,
+}
+
+impl SubtitleTags {
+    /// Reads the trailing dot-separated tags of a file stem (the name without
+    /// its extension): `Show - 01.pt-BR.forced` gives `pt-BR` and `forced`.
+    /// Scanning stops at the first segment that is not a tag, or a second
+    /// language, and the first segment is always left as the name.
+    ///
+    /// A two-letter code that is not all lowercase could be a word of the
+    /// title (`The.Movie.It`), so it only counts next to a flag or after a
+    /// segment with a number in it (`Show.S01E05.IT`, `Movie.2019.It`).
+    pub fn parse(stem: &str) -> Self {
+        let mut tags = SubtitleTags::default();
+        let segments: Vec<&str> = stem.split('.').collect();
+        for i in (1..segments.len()).rev() {
+            let segment = segments[i];
+            if let Some(flag) = SubtitleFlag::parse(segment) {
+                tags.flags.insert(0, flag);
+            } else if tags.language.is_none()
+                && (!may_be_a_word(segment) || !tags.flags.is_empty() || is_boundary(segments[i - 1]))
+                && let Some(language) = Language::parse(segment)
+            {
+                tags.language = Some(language);
+            } else {
+                break;
+            }
+        }
+        tags
+    }
+    
+    /// Reads a track name as found in `Subs/<episode>/` folders of releases:
+    /// `2_English`, `3_English_SDH`, `English`. Gives no tags unless every
+    /// word after the track number is a language or flag.
+    pub fn parse_track(stem: &str) -> Self {
+        let mut tags = SubtitleTags::default();
+        let name = stem.trim_start_matches(|c: char| c.is_ascii_digit()).trim_start_matches(['_', ' ', '-']);
+        for word in name.split(['_', ' ']).filter(|word| !word.is_empty()) {
+            if let Some(flag) = SubtitleFlag::parse(word) {
+                tags.flags.push(flag);
+            } else if tags.language.is_none()
+                && let Some(language) = Language::parse(word)
+            {
+                tags.language = Some(language);
+            } else {
+                return SubtitleTags::default();
+            }
+        }
+        tags
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.language.is_none() && self.flags.is_empty()
+    }
+    
+    /// The tags as a file name suffix, language first: `.ja.forced`, or an empty string.
+    pub fn suffix(&self, style: LanguageStyle) -> String {
+        let mut suffix = String::new();
+        if let Some(language) = &self.language {
+            suffix.push('.');
+            suffix.push_str(&language.code(style));
+        }
+        for flag in &self.flags {
+            suffix.push('.');
+            suffix.push_str(flag.as_str());
+        }
+        suffix
+    }
+}
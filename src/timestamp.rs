@@ -0,0 +1,109 @@
+// Timestamp parsing and formatting for every supported subtitle format.
+// All timestamps are handled internally as milliseconds.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// `H:MM:SS,mmm` with whatever field widths and fraction separator a file used.
+static SRT_TIMESTAMP_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d+):(\d{1,2}):(\d{1,2})(?:[,.:](\d+))?$").unwrap());
+
+/// Parses an SRT timestamp (`HH:MM:SS,mmm`) into milliseconds.
+///
+/// Also reads the variants found in real files: a `.` or `:` before the
+/// fraction, fields without leading zeros, fewer or more than three fraction
+/// digits (`0:00:01,5` is 1.5 seconds) and no fraction at all.
+pub fn parse_timestamp_srt(ts: &str) -> Option<i64> {
+    let caps = SRT_TIMESTAMP_RE.captures(ts)?;
+    let hours: i64 = caps[1].parse().ok()?;
+    let minutes: i64 = caps[2].parse().ok()?;
+    let seconds: i64 = caps[3].parse().ok()?;
+    let millis = match caps.get(4) {
+        Some(fraction) => {
+            let digits: String = fraction.as_str().chars().chain("00".chars()).take(3).collect();
+            digits.parse().ok()?
+        }
+        None => 0,
+    };
+    
+    // Minutes, seconds and millis are at most two and three digits; hours are unbounded
+    hours.checked_mul(3600000)?.checked_add(minutes * 60000 + seconds * 1000 + millis)
+}
+
+/// Whether `ts` is written like an SRT timestamp, even one [`parse_timestamp_srt`]
+/// cannot read because it is out of range.
+pub(crate) fn is_timestamp_srt(ts: &str) -> bool {
+    SRT_TIMESTAMP_RE.is_match(ts)
+}
+
+/// Parses an ASS timestamp (`H:MM:SS.CC`) into milliseconds.
+pub fn parse_timestamp_ass(ts: &str) -> Option<i64> {
+    // ASS format: H:MM:SS.CC (centiseconds, not milliseconds)
+    let parts: Vec<&str> = ts.split(&[':', '.'][..]).collect();
+    if parts.len() != 4 {
+        return None;
+    }
+    
+    let hours: i64 = parts[0].parse().ok()?;
+    let minutes: i64 = parts[1].parse().ok()?;
+    let seconds: i64 = parts[2].parse().ok()?;
+    let centiseconds: i64 = parts[3].parse().ok()?;
+    
+    Some(hours * 3600000 + minutes * 60000 + seconds * 1000 + centiseconds * 10)
+}
+
+/// Formats milliseconds as an SRT timestamp (`HH:MM:SS,mmm`).
+pub fn format_timestamp_srt(ms: i64) -> String {
+    let hours = ms / 3600000;
+    let minutes = (ms % 3600000) / 60000;
+    let seconds = (ms % 60000) / 1000;
+    let millis = ms % 1000;
+    
+    format!("{:02}:{:02}:{:02},{:03}", hours, minutes, seconds, millis)
+}
+
+/// Formats milliseconds as an ASS timestamp (`H:MM:SS.CC`).
+pub fn format_timestamp_ass(ms: i64) -> String {
+    let hours = ms / 3600000;
+    let minutes = (ms % 3600000) / 60000;
+    let seconds = (ms % 60000) / 1000;
+    let centiseconds = (ms % 1000) / 10;
+    
+    format!("{}:{:02}:{:02}.{:02}", hours, minutes, seconds, centiseconds)
+}
+
+/// Parses a WebVTT timestamp (`HH:MM:SS.mmm` or `MM:SS.mmm`) into milliseconds.
+pub fn parse_timestamp_vtt(ts: &str) -> Option<i64> {
+    let (clock, millis) = ts.split_once('.')?;
+    let parts: Vec<&str> = clock.split(':').collect();
+    let (hours, minutes, seconds): (i64, i64, i64) = match parts.as_slice() {
+        [h, m, s] => (h.parse().ok()?, m.parse().ok()?, s.parse().ok()?),
+        [m, s] => (0, m.parse().ok()?, s.parse().ok()?),
+        _ => return None,
+    };
+    if millis.len() != 3 {
+        return None;
+    }
+    let millis: i64 = millis.parse().ok()?;
+    
+    Some(hours * 3600000 + minutes * 60000 + seconds * 1000 + millis)
+}
+
+/// Formats milliseconds as a WebVTT timestamp (`HH:MM:SS.mmm`).
+pub fn format_timestamp_vtt(ms: i64) -> String {
+    let hours = ms / 3600000;
+    let minutes = (ms % 3600000) / 60000;
+    let seconds = (ms % 60000) / 1000;
+    let millis = ms % 1000;
+    
+    format!("{:02}:{:02}:{:02}.{:03}", hours, minutes, seconds, millis)
+}
+
+/// Parses a timestamp written in any supported format (SRT, WebVTT or ASS).
+pub fn parse_timestamp_any(ts: &str) -> Option<i64> {
+    let ts = ts.trim();
+    parse_timestamp_srt(ts)
+        .or_else(|| parse_timestamp_vtt(ts))
+        .or_else(|| parse_timestamp_ass(ts))
+}
@@ -0,0 +1,138 @@
+// Exact time transforms applied to cue timestamps
+
+use std::fmt;
+
+/// An exact rational number used as a time scale factor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ratio {
+    pub num: i64,
+    pub den: i64,
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+fn gcd_i128(a: i128, b: i128) -> i128 {
+    if b == 0 { a.abs() } else { gcd_i128(b, a % b) }
+}
+
+impl Ratio {
+    pub const ONE: Ratio = Ratio { num: 1, den: 1 };
+    
+    /// Creates a reduced ratio with a positive denominator. Returns `None` if `den` is zero.
+    pub fn new(num: i64, den: i64) -> Option<Self> {
+        if den == 0 {
+            return None;
+        }
+        let g = gcd(num, den).max(1);
+        let sign = den.signum();
+        Some(Ratio { num: sign * num / g, den: sign * den / g })
+    }
+    
+    /// Parses a decimal (`1.0427`) or fraction (`25/24`, `24000/1001`) without going through floats.
+    pub fn parse(s: &str) -> Option<Self> {
+        if let Some((num, den)) = s.split_once('/') {
+            let num = Ratio::parse(num.trim())?;
+            let den = Ratio::parse(den.trim())?;
+            return num.checked_div(den);
+        }
+        
+        let s = s.trim();
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+        if int_part.is_empty() && frac_part.is_empty() {
+            return None;
+        }
+        if !int_part.chars().chain(frac_part.chars()).all(|c| c.is_ascii_digit()) || frac_part.len() > 12 {
+            return None;
+        }
+        
+        let den = 10i64.pow(frac_part.len() as u32);
+        let int_value: i64 = if int_part.is_empty() { 0 } else { int_part.parse().ok()? };
+        let frac_value: i64 = if frac_part.is_empty() { 0 } else { frac_part.parse().ok()? };
+        let num = int_value.checked_mul(den)?.checked_add(frac_value)?;
+        Ratio::new(if negative { -num } else { num }, den)
+    }
+    
+    pub fn checked_div(self, other: Ratio) -> Option<Self> {
+        let num = (self.num as i128) * (other.den as i128);
+        let den = (self.den as i128) * (other.num as i128);
+        Ratio::new(i64::try_from(num).ok()?, i64::try_from(den).ok()?)
+    }
+    
+    pub fn checked_mul(self, other: Ratio) -> Option<Self> {
+        let num = (self.num as i128) * (other.num as i128);
+        let den = (self.den as i128) * (other.den as i128);
+        let g = gcd_i128(num, den).max(1);
+        Ratio::new(i64::try_from(num / g).ok()?, i64::try_from(den / g).ok()?)
+    }
+    
+    /// Multiplies `ms` by the ratio, rounding to the nearest millisecond (halves round up).
+    pub fn apply(self, ms: i64) -> i64 {
+        let n = ms as i128 * self.num as i128;
+        let den = self.den as i128;
+        (2 * n + den).div_euclid(2 * den) as i64
+    }
+    
+    pub fn as_f64(self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+}
+
+impl fmt::Display for Ratio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{} (≈{:.6})", self.num, self.den, self.as_f64())
+        }
+    }
+}
+
+/// Parses a frame rate. The rounded NTSC rates (23.976, 29.97, 59.94, ...) are
+/// read as their exact `N*1000/1001` values so conversions don't drift.
+pub fn parse_fps(s: &str) -> Option<Ratio> {
+    let ntsc = match s.trim() {
+        "23.976" | "23.98" => Some(24000),
+        "29.97" => Some(30000),
+        "47.952" => Some(48000),
+        "59.94" => Some(60000),
+        _ => None,
+    };
+    let fps = match ntsc {
+        Some(num) => Ratio::new(num, 1001)?,
+        None => Ratio::parse(s)?,
+    };
+    (fps.num > 0).then_some(fps)
+}
+
+/// Scale factor that retimes subtitles made for a `from` fps release onto a `to` fps video.
+pub fn fps_scale(from: Ratio, to: Ratio) -> Option<Ratio> {
+    from.checked_div(to)
+}
+
+/// A linear time mapping `t -> target + (t - origin) * scale`.
+///
+/// Each timestamp is mapped from its original value with exact rational
+/// arithmetic, so rounding errors never accumulate across a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinearMap {
+    pub origin: i64,
+    pub target: i64,
+    pub scale: Ratio,
+}
+
+impl LinearMap {
+    /// Scales timestamps around zero, then adds `offset_ms`.
+    pub fn new(scale: Ratio, offset_ms: i64) -> Self {
+        LinearMap { origin: 0, target: offset_ms, scale }
+    }
+    
+    pub fn apply(&self, ms: i64) -> i64 {
+        self.target + self.scale.apply(ms - self.origin)
+    }
+}
@@ -0,0 +1,103 @@
+// Voice activity detection on decoded audio
+
+use crate::align::merge_intervals;
+use crate::audio::Audio;
+
+/// Tuning for [`detect_speech`].
+#[derive(Debug, Clone)]
+pub struct VadOptions {
+    /// Analysis frame length.
+    pub frame_ms: i64,
+    /// Minimum level above the noise floor, in dB, for a frame to count as speech.
+    pub min_margin_db: f32,
+    /// Pauses shorter than this are bridged.
+    pub min_silence_ms: i64,
+    /// Speech bursts shorter than this are dropped.
+    pub min_speech_ms: i64,
+}
+
+impl Default for VadOptions {
+    fn default() -> Self {
+        VadOptions { frame_ms: 20, min_margin_db: 6.0, min_silence_ms: 300, min_speech_ms: 200 }
+    }
+}
+
+/// Band-limits the signal to the main voice range (~200-3400 Hz) with
+/// one-pole filters, so rumble and hiss weigh less in the frame energy.
+fn voice_band(audio: &Audio) -> Vec<f32> {
+    let rate = audio.sample_rate as f32;
+    let dt = 1.0 / rate;
+    let high_rc = 1.0 / (2.0 * std::f32::consts::PI * 200.0);
+    let low_rc = 1.0 / (2.0 * std::f32::consts::PI * 3400.0);
+    let high_alpha = high_rc / (high_rc + dt);
+    let low_alpha = dt / (low_rc + dt);
+    
+    let mut filtered = Vec::with_capacity(audio.samples.len());
+    let (mut prev_in, mut high, mut low) = (0.0f32, 0.0f32, 0.0f32);
+    for &sample in &audio.samples {
+        high = high_alpha * (high + sample - prev_in);
+        prev_in = sample;
+        low += low_alpha * (high - low);
+        filtered.push(low);
+    }
+    filtered
+}
+
+fn percentile(sorted: &[f32], p: f32) -> f32 {
+    sorted[((sorted.len() - 1) as f32 * p) as usize]
+}
+
+/// Detects speech in `audio`, returning merged (start, end) intervals in milliseconds.
+///
+/// Frames are classified by their voice-band energy against an adaptive
+/// threshold between the noise floor and the loud end of the track.
+pub fn detect_speech(audio: &Audio, options: &VadOptions) -> Vec<(i64, i64)> {
+    let frame_len = (audio.sample_rate as i64 * options.frame_ms / 1000).max(1) as usize;
+    let filtered = voice_band(audio);
+    let energies: Vec<f32> = filtered
+        .chunks(frame_len)
+        .map(|frame| {
+            let power = frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32;
+            10.0 * (power + 1e-10).log10()
+        })
+        .collect();
+    if energies.is_empty() {
+        return Vec::new();
+    }
+    
+    let mut sorted = energies.clone();
+    sorted.sort_by(f32::total_cmp);
+    let floor = percentile(&sorted, 0.1);
+    let loud = percentile(&sorted, 0.9);
+    let threshold = floor + options.min_margin_db.max((loud - floor) * 0.35);
+    
+    let frame_time = |i: usize| (i * frame_len) as i64 * 1000 / audio.sample_rate as i64;
+    let mut raw = Vec::new();
+    let mut start = None;
+    for (i, &energy) in energies.iter().enumerate() {
+        let time = frame_time(i);
+        match (energy > threshold, start) {
+            (true, None) => start = Some(time),
+            (false, Some(s)) => {
+                raw.push((s, time));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        raw.push((s, frame_time(energies.len())));
+    }
+    
+    // Bridge short pauses, then drop the clicks and bangs that are left
+    let bridged = merge_intervals(
+        raw.into_iter()
+            .map(|(start, end)| (start, end + options.min_silence_ms))
+            .collect(),
+    );
+    bridged
+        .into_iter()
+        .map(|(start, end)| (start, end - options.min_silence_ms))
+        .filter(|(start, end)| end - start >= options.min_speech_ms)
+        .collect()
+}
@@ -0,0 +1,104 @@
+// WebVTT (.vtt) parsing and serialization
+
+use crate::subtitle::{Block, Cue, CueMeta, SubtitleDocument, SubtitleFormat, TextLayout};
+use crate::timestamp::{format_timestamp_vtt, parse_timestamp_vtt};
+
+/// Splits a timing line into start, end and the cue settings that follow the end time.
+fn parse_timing_line(line: &str) -> Option<(i64, i64, String)> {
+    let (start, rest) = line.split_once(" --> ")?;
+    let rest = rest.trim_start();
+    let (end, settings) = match rest.find(char::is_whitespace) {
+        Some(idx) => (&rest[..idx], rest[idx..].trim()),
+        None => (rest, ""),
+    };
+    Some((parse_timestamp_vtt(start.trim())?, parse_timestamp_vtt(end)?, settings.to_string()))
+}
+
+/// Parses WebVTT content into a [`SubtitleDocument`].
+///
+/// The `WEBVTT` header and NOTE/STYLE/REGION blocks are kept verbatim.
+pub fn parse(content: &str) -> SubtitleDocument {
+    let (layout, lines) = TextLayout::split(content);
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut i = 0;
+    
+    while i < lines.len() {
+        let Some((start, end, settings)) = parse_timing_line(lines[i]) else {
+            blocks.push(Block::Raw(lines[i].to_string()));
+            i += 1;
+            continue;
+        };
+        
+        // A non-blank line that starts the block is the cue identifier
+        let starts_block = |blocks: &[Block]| match blocks.len() {
+            0 => true,
+            // A cue right under the header, without a blank line: the header is not its identifier
+            1 => !matches!(&blocks[0], Block::Raw(line) if line.starts_with("WEBVTT")),
+            n => matches!(&blocks[n - 2], Block::Raw(line) if line.trim().is_empty()),
+        };
+        let identifier = match blocks.last() {
+            Some(Block::Raw(line)) if !line.trim().is_empty() && starts_block(&blocks) => match blocks.pop() {
+                Some(Block::Raw(line)) => Some(line),
+                _ => None,
+            },
+            _ => None,
+        };
+        
+        let timing = lines[i];
+        i += 1;
+        let mut text_lines = Vec::new();
+        while i < lines.len() && !lines[i].trim().is_empty() {
+            text_lines.push(lines[i]);
+            i += 1;
+        }
+        
+        let meta = CueMeta::Vtt { identifier, settings };
+        let mut cue = Cue { start, end, text: text_lines.join("\n"), meta, source_line: None };
+        cue.keep_source_line(timing_line(&cue), timing);
+        blocks.push(Block::Cue(cue));
+    }
+    
+    SubtitleDocument { format: SubtitleFormat::Vtt, blocks, layout }
+}
+
+/// The timing line of a cue, with its settings.
+fn timing_line(cue: &Cue) -> String {
+    let mut line = format!("{} --> {}", format_timestamp_vtt(cue.start), format_timestamp_vtt(cue.end));
+    if let CueMeta::Vtt { settings, .. } = &cue.meta
+        && !settings.is_empty()
+    {
+        line.push(' ');
+        line.push_str(settings);
+    }
+    line
+}
+
+/// Serializes a document as WebVTT.
+pub fn serialize(doc: &SubtitleDocument) -> String {
+    let mut result = String::new();
+    
+    for block in &doc.blocks {
+        match block {
+            Block::Raw(line) => {
+                result.push_str(line);
+                result.push('\n');
+            }
+            Block::Cue(cue) => {
+                if let CueMeta::Vtt { identifier: Some(identifier), .. } = &cue.meta {
+                    result.push_str(identifier);
+                    result.push('\n');
+                }
+                result.push_str(&cue.source_or(timing_line(cue)));
+                result.push('\n');
+                if !cue.text.is_empty() {
+                    for line in cue.text.split('\n') {
+                        result.push_str(line);
+                        result.push('\n');
+                    }
+                }
+            }
+        }
+    }
+    
+    doc.layout.apply(result)
+}
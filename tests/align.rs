@@ -0,0 +1,85 @@
+// Offset, framerate and drift detection from speech intervals
+
+use subsync::align::{align, AlignOptions};
+use subsync::{LinearMap, Ratio};
+
+/// Irregular speech over about twenty minutes, from a fixed pseudo-random sequence.
+fn speech(seed: u64) -> Vec<(i64, i64)> {
+    let mut state = seed;
+    let mut next = |range: i64| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 33) as i64 % range
+    };
+    let mut intervals = Vec::new();
+    let mut time = 5_000;
+    while time < 1_200_000 {
+        let length = 800 + next(4_000);
+        intervals.push((time, time + length));
+        time += length + 300 + next(6_000);
+    }
+    intervals
+}
+
+/// The intervals a subtitle would have if `map` is what puts it back on `reference`.
+fn unmap(reference: &[(i64, i64)], map: impl Fn(i64) -> i64) -> Vec<(i64, i64)> {
+    reference.iter().map(|&(start, end)| (map(start), map(end))).collect()
+}
+
+#[test]
+fn finds_a_known_offset() {
+    let reference = speech(1);
+    for offset in [3_250, -47_010, 0] {
+        let target = unmap(&reference, |ms| ms - offset);
+        let alignment = align(&reference, &target, &AlignOptions::default()).unwrap();
+        assert_eq!(alignment.offset_ms, offset);
+        assert_eq!(alignment.scale, Ratio::ONE);
+        assert!(alignment.confidence > 0.99, "{:?}", alignment);
+    }
+}
+
+#[test]
+fn finds_a_known_framerate_scale() {
+    let reference = speech(2);
+    // Subtitle timed for 23.976 fps played at 25 fps
+    let scale = Ratio::new(25 * 1001, 24_000).unwrap();
+    let target = unmap(&reference, |ms| (ms - 12_000) * 24_000 / 25_025);
+    let options = AlignOptions { detect_scale: true, ..AlignOptions::default() };
+    let alignment = align(&reference, &target, &options).unwrap();
+    assert_eq!(alignment.scale, scale);
+    assert!((alignment.offset_ms - 12_000).abs() <= 2, "{:?}", alignment);
+    assert!(alignment.confidence > 0.95, "{:?}", alignment);
+    
+    // Without scale detection the best offset only matches part of the file
+    let unscaled = align(&reference, &target, &AlignOptions::default()).unwrap();
+    assert_eq!(unscaled.scale, Ratio::ONE);
+    assert!(unscaled.confidence < alignment.confidence);
+}
+
+#[test]
+fn removes_slow_drift() {
+    let reference = speech(3);
+    // 0.05% faster than the reference, about 0.6 s off by the end
+    let target = unmap(&reference, |ms| (ms - 2_000) * 10_000 / 10_005);
+    let options = AlignOptions { detect_drift: true, ..AlignOptions::default() };
+    let alignment = align(&reference, &target, &options).unwrap();
+    assert_ne!(alignment.scale, Ratio::ONE);
+    
+    let map = LinearMap::new(alignment.scale, alignment.offset_ms);
+    for (&(expected, _), &(start, _)) in reference.iter().zip(&target) {
+        assert!((map.apply(start) - expected).abs() <= 50, "{} -> {} for {}", start, map.apply(start), expected);
+    }
+    let fixed = align(&reference, &target, &AlignOptions::default()).unwrap();
+    assert!(alignment.confidence > fixed.confidence, "{:?} vs {:?}", alignment, fixed);
+}
+
+#[test]
+fn reports_missing_or_unrelated_speech() {
+    let reference = speech(4);
+    assert_eq!(align(&reference, &[], &AlignOptions::default()), None);
+    assert_eq!(align(&[], &reference, &AlignOptions::default()), None);
+    // Empty cues carry no speech either
+    assert_eq!(align(&reference, &[(1_000, 1_000)], &AlignOptions::default()), None);
+    
+    let unrelated = align(&reference, &speech(5), &AlignOptions::default()).unwrap();
+    assert!(unrelated.confidence < 0.3, "{:?}", unrelated);
+}
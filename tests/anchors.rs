@@ -0,0 +1,116 @@
+// Syncing from anchor points: two-point linear maps and piecewise maps through several
+
+use subsync::{Anchor, AnchorMode, AnchorPoint, PiecewiseMap, SubtitleDocument, SubtitleFormat};
+
+/// Three cues, starting at 1 s, 10 s and 20 s.
+fn document() -> SubtitleDocument {
+    let content = "1\n00:00:01,000 --> 00:00:02,000\nOne\n\n2\n00:00:10,000 --> 00:00:11,000\nTwo\n\n3\n00:00:20,000 --> 00:00:21,000\nThree\n";
+    SubtitleDocument::parse(content, SubtitleFormat::Srt)
+}
+
+fn anchor(s: &str) -> Anchor {
+    Anchor::parse(s).unwrap_or_else(|| panic!("cannot parse anchor '{}'", s))
+}
+
+#[test]
+fn parses_anchors() {
+    assert_eq!(anchor("#12=0:01:04.10"), Anchor { old: AnchorPoint::Cue(12), new: 64_100 });
+    assert_eq!(anchor("00:01:02,500=00:01:04,100"), Anchor { old: AnchorPoint::Time(62_500), new: 64_100 });
+    for invalid in ["#0=00:00:01,000", "#x=00:00:01,000", "00:00:01,000", "#1=soon"] {
+        assert_eq!(Anchor::parse(invalid), None, "{}", invalid);
+    }
+}
+
+#[test]
+fn maps_cue_and_time_anchors_alike() {
+    let doc = document();
+    let by_cue = doc.two_point_sync(anchor("#1=00:00:02,000"), anchor("#3=00:00:41,000")).unwrap();
+    let by_time = doc.two_point_sync(anchor("00:00:01,000=00:00:02,000"), anchor("00:00:20,000=00:00:41,000")).unwrap();
+    let reversed = doc.two_point_sync(anchor("#3=00:00:41,000"), anchor("00:00:01,000=00:00:02,000")).unwrap();
+    assert_eq!(by_cue, by_time);
+    assert_eq!(by_cue, reversed);
+    
+    // Both anchors land exactly; between them time is stretched by 39/19
+    assert_eq!(by_cue.apply(1_000), 2_000);
+    assert_eq!(by_cue.apply(20_000), 41_000);
+    assert_eq!(by_cue.apply(10_000), 20_474);
+    assert_eq!(by_cue.apply(0), -53);
+}
+
+#[test]
+fn rejects_unusable_anchors() {
+    let doc = document();
+    let error = doc.two_point_sync(anchor("#1=00:00:02,000"), anchor("00:00:01,000=00:00:05,000")).unwrap_err();
+    assert_eq!(error, "both anchors are at 00:00:01,000");
+    let error = doc.two_point_sync(anchor("#1=00:00:02,000"), anchor("#4=00:00:41,000")).unwrap_err();
+    assert_eq!(error, "cue #4 does not exist (file has 3 cues)");
+    let out_of_order = doc.two_point_sync(anchor("#1=00:00:05,000"), anchor("#2=00:00:05,000"));
+    assert!(out_of_order.is_err());
+    
+    let zero = Anchor { old: AnchorPoint::Cue(0), new: 0 };
+    assert!(doc.two_point_sync(zero, anchor("#2=00:00:05,000")).is_err());
+    let empty = SubtitleDocument::parse("", SubtitleFormat::Srt);
+    assert!(empty.two_point_sync(anchor("#1=00:00:02,000"), anchor("#2=00:00:05,000")).is_err());
+}
+
+/// Anchors 10 s → 12 s, 60 s → 61 s and 100 s → 105 s, given out of order.
+fn piecewise(mode: AnchorMode) -> PiecewiseMap {
+    PiecewiseMap::new(vec![(60_000, 61_000), (10_000, 12_000), (100_000, 105_000)], mode).unwrap()
+}
+
+#[test]
+fn steps_by_the_offset_of_the_previous_anchor() {
+    let map = piecewise(AnchorMode::Step);
+    assert_eq!(map.points(), [(10_000, 12_000), (60_000, 61_000), (100_000, 105_000)]);
+    // Before the first anchor its offset applies
+    assert_eq!(map.apply(5_000), 7_000);
+    assert_eq!(map.apply(10_000), 12_000);
+    assert_eq!(map.apply(59_999), 61_999);
+    assert_eq!(map.apply(60_000), 61_000);
+    assert_eq!(map.apply(80_000), 81_000);
+    // After the last anchor its offset goes on
+    assert_eq!(map.apply(200_000), 205_000);
+}
+
+#[test]
+fn interpolates_between_anchors() {
+    let map = piecewise(AnchorMode::Interpolate);
+    for (old, new) in [(10_000, 12_000), (60_000, 61_000), (100_000, 105_000)] {
+        assert_eq!(map.apply(old), new);
+    }
+    // Before the first and after the last anchor the outer segments are extended
+    assert_eq!(map.apply(5_000), 7_100);
+    assert_eq!(map.apply(30_000), 31_600);
+    assert_eq!(map.apply(80_000), 83_000);
+    assert_eq!(map.apply(200_000), 215_000);
+    
+    let single = PiecewiseMap::new(vec![(10_000, 12_000)], AnchorMode::Interpolate).unwrap();
+    assert_eq!(single.apply(0), 2_000);
+}
+
+#[test]
+fn retimes_cues_through_anchors() {
+    let content = "1\n00:00:01,000 --> 00:00:02,000\nOne\n\n2\n00:00:59,500 --> 00:01:00,500\nAcross the cut\n\n3\n00:01:10,000 --> 00:01:11,000\nThree\n";
+    let doc = SubtitleDocument::parse(content, SubtitleFormat::Srt);
+    let anchors = [anchor("#1=00:00:03,000"), anchor("00:01:00,000=00:00:59,000")];
+    let times = |mode| {
+        let mut doc = doc.clone();
+        doc.apply_piecewise(&doc.anchor_sync(&anchors, mode).unwrap());
+        doc.cues().map(|cue| (cue.start, cue.end)).collect::<Vec<_>>()
+    };
+    // A cue spanning the cut keeps its length and the offset at its start
+    assert_eq!(times(AnchorMode::Step), [(3_000, 4_000), (61_500, 62_500), (69_000, 70_000)]);
+    assert_eq!(times(AnchorMode::Interpolate), [(3_000, 3_949), (58_525, 59_475), (68_492, 69_441)]);
+}
+
+#[test]
+fn rejects_unusable_anchor_sets() {
+    assert!(PiecewiseMap::new(Vec::new(), AnchorMode::Step).is_err());
+    assert!(PiecewiseMap::new(vec![(10_000, 12_000), (10_000, 15_000)], AnchorMode::Step).is_err());
+    // Step mode allows a later anchor to move back, interpolating through it would reverse time
+    let backwards = vec![(10_000, 12_000), (60_000, 11_000)];
+    assert!(PiecewiseMap::new(backwards.clone(), AnchorMode::Step).is_ok());
+    assert!(PiecewiseMap::new(backwards, AnchorMode::Interpolate).is_err());
+    let error = document().anchor_sync(&[anchor("#9=00:00:01,000")], AnchorMode::Step).unwrap_err();
+    assert_eq!(error, "cue #9 does not exist (file has 3 cues)");
+}
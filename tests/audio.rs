@@ -0,0 +1,255 @@
+// Audio decoding of hand-built WAV and FLAC streams, and speech detection on synthetic signals
+
+use std::fs;
+use std::path::PathBuf;
+
+use subsync::audio::{decode_flac, load_audio, Audio};
+use subsync::vad::{detect_speech, VadOptions};
+
+/// Samples shared by the test streams, at 8 kHz so they are not downsampled.
+const SIGNAL: [i64; 16] = [0, 120, 250, 380, 500, 600, 680, 740, 760, 740, 680, 600, 500, 380, 250, 120];
+
+fn temp_file(name: &str, bytes: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("subsync-{}-{}", std::process::id(), name));
+    fs::write(&path, bytes).unwrap();
+    path
+}
+
+fn wav(bits: u16, samples: &[i64]) -> Vec<u8> {
+    let data: Vec<u8> = samples
+        .iter()
+        .flat_map(|&s| (s as i32).to_le_bytes()[..bits as usize / 8].to_vec())
+        .collect();
+    let mut bytes = b"RIFF".to_vec();
+    bytes.extend((36 + data.len() as u32).to_le_bytes());
+    bytes.extend(b"WAVEfmt ");
+    bytes.extend(16u32.to_le_bytes());
+    bytes.extend(1u16.to_le_bytes()); // PCM
+    bytes.extend(1u16.to_le_bytes()); // mono
+    bytes.extend(8000u32.to_le_bytes());
+    bytes.extend((8000 * bits as u32 / 8).to_le_bytes());
+    bytes.extend((bits / 8).to_le_bytes());
+    bytes.extend(bits.to_le_bytes());
+    bytes.extend(b"data");
+    bytes.extend((data.len() as u32).to_le_bytes());
+    bytes.extend(data);
+    bytes
+}
+
+#[test]
+fn reads_16_and_24_bit_wav() {
+    for (bits, scale) in [(16, 1i64), (24, 256)] {
+        let samples: Vec<i64> = SIGNAL.iter().chain(&[-32768, 32767]).map(|s| s * scale).collect();
+        let path = temp_file(&format!("{}.wav", bits), &wav(bits, &samples));
+        let audio = load_audio(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        
+        assert_eq!(audio.sample_rate, 8000);
+        let expected: Vec<f32> = samples.iter().map(|&s| s as f32 / (1i64 << (bits - 1)) as f32).collect();
+        assert_eq!(audio.samples, expected, "{} bits", bits);
+    }
+}
+
+#[test]
+fn rejects_oversized_wav_fmt_chunk() {
+    let mut bytes = wav(16, &SIGNAL);
+    bytes[16..20].copy_from_slice(&0xFFFF_FFF0u32.to_le_bytes());
+    let path = temp_file("huge-fmt.wav", &bytes);
+    let result = load_audio(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(result.is_err());
+}
+
+/// MSB-first bit writer for building FLAC streams.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: i64, bits: u32) {
+        for i in (0..bits).rev() {
+            if self.bits.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            let bit = ((value >> i) & 1) as u8;
+            *self.bytes.last_mut().unwrap() |= bit << (7 - self.bits % 8);
+            self.bits += 1;
+        }
+    }
+    
+    fn align(&mut self) {
+        self.bits = self.bits.next_multiple_of(8);
+    }
+    
+    /// Rice-codes residuals in a single partition with parameter `param`.
+    fn residual(&mut self, residuals: &[i64], param: u32) {
+        self.write(0, 2); // 4-bit parameters
+        self.write(0, 4); // partition order
+        self.write(param as i64, 4);
+        for &r in residuals {
+            let folded = (r << 1) ^ (r >> 63);
+            for _ in 0..folded >> param {
+                self.write(0, 1);
+            }
+            self.write(1, 1);
+            self.write(folded, param);
+        }
+    }
+}
+
+/// A mono 16-bit stream at 8 kHz, given its encoded subframes.
+fn flac(subframes: &[fn(&mut BitWriter)]) -> Vec<u8> {
+    let mut w = BitWriter::default();
+    w.write(0x80, 8); // last metadata block, STREAMINFO
+    w.write(34, 24);
+    w.write(16, 16);
+    w.write(16, 16);
+    w.write(0, 24);
+    w.write(0, 24);
+    w.write(8000, 20);
+    w.write(0, 3); // one channel
+    w.write(15, 5); // 16 bits
+    w.write(0, 36);
+    w.write(0, 64);
+    w.write(0, 64);
+    
+    for (i, subframe) in subframes.iter().enumerate() {
+        w.write(0x3FFE, 14);
+        w.write(0, 2);
+        w.write(6, 4); // 8-bit block size at the end of the header
+        w.write(0, 4); // sample rate from STREAMINFO
+        w.write(0, 4); // mono
+        w.write(0, 3); // sample size from STREAMINFO
+        w.write(0, 1);
+        w.write(i as i64, 8);
+        w.write(SIGNAL.len() as i64 - 1, 8);
+        w.write(0, 8); // CRC-8
+        subframe(&mut w);
+        w.align();
+        w.write(0, 16); // CRC-16
+    }
+    w.bytes
+}
+
+fn verbatim(w: &mut BitWriter) {
+    w.write(0, 1);
+    w.write(1, 6);
+    w.write(0, 1);
+    for &s in &SIGNAL {
+        w.write(s, 16);
+    }
+}
+
+/// Fixed predictor of order 2.
+fn fixed(w: &mut BitWriter) {
+    w.write(0, 1);
+    w.write(10, 6);
+    w.write(0, 1);
+    w.write(SIGNAL[0], 16);
+    w.write(SIGNAL[1], 16);
+    let residuals: Vec<i64> = (2..SIGNAL.len()).map(|i| SIGNAL[i] - 2 * SIGNAL[i - 1] + SIGNAL[i - 2]).collect();
+    w.residual(&residuals, 3);
+}
+
+/// LPC of order 1 with coefficient 1.
+fn lpc(w: &mut BitWriter) {
+    w.write(0, 1);
+    w.write(32, 6);
+    w.write(0, 1);
+    w.write(SIGNAL[0], 16);
+    w.write(3, 4); // 4-bit precision
+    w.write(0, 5); // no shift
+    w.write(1, 4);
+    let residuals: Vec<i64> = (1..SIGNAL.len()).map(|i| SIGNAL[i] - SIGNAL[i - 1]).collect();
+    w.residual(&residuals, 6);
+}
+
+/// LPC whose prediction grows past 64 bits.
+fn exploding_lpc(w: &mut BitWriter) {
+    w.write(0, 1);
+    w.write(32, 6);
+    w.write(0, 1);
+    w.write(30000, 16);
+    w.write(15, 4); // 16-bit precision
+    w.write(0, 5);
+    w.write(30000, 16);
+    w.residual(&[0; 15], 0);
+}
+
+/// Claims more wasted bits than the sample has.
+fn too_many_wasted_bits(w: &mut BitWriter) {
+    w.write(0, 1);
+    w.write(0, 6);
+    w.write(1, 1);
+    w.write(0, 40);
+    w.write(1, 1);
+    w.write(0, 16);
+}
+
+fn samples(audio: &Audio) -> Vec<i64> {
+    audio.samples.iter().map(|&s| (s * 32768.0) as i64).collect()
+}
+
+#[test]
+fn decodes_flac_subframes() {
+    let bytes = flac(&[verbatim, fixed, lpc]);
+    let audio = decode_flac(&bytes[..]).unwrap();
+    assert_eq!(audio.sample_rate, 8000);
+    assert_eq!(samples(&audio), SIGNAL.repeat(3));
+    
+    let mut file = b"fLaC".to_vec();
+    file.extend(&bytes);
+    let path = temp_file("subframes.flac", &file);
+    let audio = load_audio(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(samples(&audio), SIGNAL.repeat(3));
+}
+
+#[test]
+fn skips_damaged_flac_frames() {
+    let bytes = flac(&[verbatim, exploding_lpc, too_many_wasted_bits, lpc]);
+    let audio = decode_flac(&bytes[..]).unwrap();
+    assert_eq!(samples(&audio), SIGNAL.repeat(2));
+}
+
+#[test]
+fn survives_truncated_flac() {
+    let bytes = flac(&[verbatim, fixed]);
+    for end in 0..bytes.len() {
+        let _ = decode_flac(&bytes[..end]);
+    }
+    let audio = decode_flac(&bytes[..bytes.len() - 4]).unwrap();
+    assert_eq!(samples(&audio), SIGNAL);
+}
+
+#[test]
+fn rejects_flac_without_sample_rate() {
+    let mut bytes = flac(&[verbatim]);
+    // Sample rate field of STREAMINFO (20 bits from byte 14)
+    bytes[14] = 0;
+    bytes[15] = 0;
+    bytes[16] &= 0x0F;
+    assert!(decode_flac(&bytes[..]).is_err());
+}
+
+fn tone(ms: usize, amplitude: f32) -> Vec<f32> {
+    (0..ms * 8).map(|i| amplitude * (i as f32 * 2.0 * std::f32::consts::PI * 1000.0 / 8000.0).sin()).collect()
+}
+
+#[test]
+fn detects_tone_between_silence() {
+    let samples = [tone(2000, 0.0), tone(2000, 0.5), tone(2000, 0.0)].concat();
+    let speech = detect_speech(&Audio { sample_rate: 8000, samples }, &VadOptions::default());
+    assert_eq!(speech.len(), 1, "{:?}", speech);
+    let (start, end) = speech[0];
+    assert!((start - 2000).abs() <= 40 && (end - 4000).abs() <= 40, "{:?}", speech);
+}
+
+#[test]
+fn finds_no_speech_in_silence() {
+    let options = VadOptions::default();
+    assert!(detect_speech(&Audio { sample_rate: 8000, samples: tone(3000, 0.0) }, &options).is_empty());
+    assert!(detect_speech(&Audio { sample_rate: 8000, samples: Vec::new() }, &options).is_empty());
+}
@@ -0,0 +1,124 @@
+// Two subtitles renamed to the same file, under each --on-conflict policy
+
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+/// A video with a subtitle from each of two groups, which both map to `Show - 01.srt`.
+fn folder(name: &str) -> PathBuf {
+    common::folder_with(
+        &format!("collision-{}", name),
+        &[
+            ("Show - 01.mkv", ""),
+            ("[A] Show - 01.srt", "1\n00:00:01,000 --> 00:00:02,000\nA\n"),
+            ("[B] Show - 01.srt", "1\n00:00:01,000 --> 00:00:02,000\nB\n"),
+        ],
+    )
+}
+
+fn run(dir: &Path, policy: Option<&str>) -> Output {
+    match policy {
+        Some(policy) => common::run_on(dir, &["--on-conflict", policy], &["1"]),
+        None => common::run_on(dir, &[], &["1"]),
+    }
+}
+
+/// Subtitle files left in the folder, with the text of their cue.
+fn subtitles(dir: &Path) -> Vec<(String, String)> {
+    let mut files: Vec<(String, String)> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "srt"))
+        .map(|path| {
+            let content = fs::read_to_string(&path).unwrap();
+            (path.file_name().unwrap().to_string_lossy().into_owned(), content.lines().nth(2).unwrap_or("").to_string())
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn pair(name: &str, text: &str) -> (String, String) {
+    (name.to_string(), text.to_string())
+}
+
+#[test]
+fn suffix_numbers_the_second_subtitle() {
+    let dir = folder("suffix");
+    let output = run(&dir, None);
+    assert!(output.status.success());
+    assert_eq!(subtitles(&dir), [pair("Show - 01.2.srt", "B"), pair("Show - 01.srt", "A")]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Show - 01.srt wanted by [A] Show - 01.srt, [B] Show - 01.srt → renamed [B] Show - 01.srt to Show - 01.2.srt"), "{}", stdout);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn skip_leaves_the_second_subtitle() {
+    let dir = folder("skip");
+    let output = run(&dir, Some("skip"));
+    assert!(output.status.success());
+    assert_eq!(subtitles(&dir), [pair("Show - 01.srt", "A"), pair("[B] Show - 01.srt", "B")]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("→ skipped [B] Show - 01.srt"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn overwrite_keeps_the_last_subtitle() {
+    let dir = folder("overwrite");
+    let output = run(&dir, Some("overwrite"));
+    assert!(output.status.success());
+    assert_eq!(subtitles(&dir), [pair("Show - 01.srt", "B")]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("→ overwritten"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fail_changes_nothing() {
+    let dir = folder("fail");
+    let output = run(&dir, Some("fail"));
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(subtitles(&dir), [pair("[A] Show - 01.srt", "A"), pair("[B] Show - 01.srt", "B")]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Show - 01.srt wanted by [A] Show - 01.srt, [B] Show - 01.srt"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// `Show - 01.srt` wants the name of `ep01.srt`, which is processed after it.
+fn owned_target_folder(name: &str) -> PathBuf {
+    common::folder_with(
+        &format!("collision-{}", name),
+        &[
+            ("ep01.mkv", ""),
+            ("ep01.srt", "1\n00:00:01,000 --> 00:00:02,000\nOwn\n"),
+            ("Show - 01.srt", "1\n00:00:05,000 --> 00:00:06,000\nOther\n"),
+        ],
+    )
+}
+
+fn read(dir: &Path, name: &str) -> String {
+    fs::read_to_string(dir.join(name)).unwrap()
+}
+
+#[test]
+fn the_subtitle_with_the_name_keeps_it() {
+    let dir = owned_target_folder("owned");
+    let output = run(&dir, None);
+    assert!(output.status.success());
+    assert_eq!(read(&dir, "ep01.srt"), "1\n00:00:02,000 --> 00:00:03,000\nOwn\n");
+    assert_eq!(read(&dir, "ep01.2.srt"), "1\n00:00:06,000 --> 00:00:07,000\nOther\n");
+    assert!(!dir.join("Show - 01.srt").exists());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("ep01.srt wanted by ep01.srt, Show - 01.srt → renamed Show - 01.srt to ep01.2.srt"), "{}", stdout);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn overwrite_shifts_the_owned_subtitle_once() {
+    let dir = owned_target_folder("owned-overwrite");
+    assert!(run(&dir, Some("overwrite")).status.success());
+    assert_eq!(subtitles(&dir), [pair("ep01.srt", "Own")]);
+    assert_eq!(read(&dir, "ep01.srt"), "1\n00:00:02,000 --> 00:00:03,000\nOwn\n");
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,46 @@
+// Temporary folders and binary runs shared by the integration tests
+
+// Each test crate uses only some of these
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// An empty folder in the system temp dir, unique to `name` and this test run.
+pub fn folder(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("subsync-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Like [`folder`], holding `files` as (name, content) pairs.
+pub fn folder_with(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = folder(name);
+    for (file, content) in files {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    dir
+}
+
+/// The SubSync binary, without the extension lists of the calling environment.
+pub fn command() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_SubSync"));
+    for variable in ["SUBSYNC_VIDEO_EXT", "SUBSYNC_SUBTITLE_EXT", "SUBSYNC_IGNORE_EXT"] {
+        command.env_remove(variable);
+    }
+    command
+}
+
+/// Runs the binary with `args`.
+pub fn run(args: &[&str]) -> Output {
+    command().args(args).output().unwrap()
+}
+
+/// Runs the binary on `dir` with `args` before it and `trailing` after it.
+pub fn run_on(dir: &Path, args: &[&str], trailing: &[&str]) -> Output {
+    command().args(args).arg(dir).args(trailing).output().unwrap()
+}
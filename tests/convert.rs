@@ -0,0 +1,56 @@
+// Conversion between SRT, ASS and WebVTT, and the inline markup in between
+
+use subsync::convert::{ass_to_html, html_to_ass};
+use subsync::{SubtitleDocument, SubtitleFormat};
+
+/// (ASS override tags, SRT markup) that convert into each other
+const MARKUP: &[(&str, &str)] = &[
+    ("{\\i1}x{\\i0}", "<i>x</i>"),
+    ("{\\b1}x{\\b0}", "<b>x</b>"),
+    ("{\\u1}x{\\u0}", "<u>x</u>"),
+    ("{\\s1}x{\\s0}", "<s>x</s>"),
+    ("{\\c&H0080FF&}x{\\c}", "<font color=\"#ff8000\">x</font>"),
+    ("a {\\i1}{\\b1}both{\\b0}{\\i0} b", "a <i><b>both</b></i> b"),
+];
+
+#[test]
+fn maps_markup_both_ways() {
+    for &(ass, html) in MARKUP {
+        assert_eq!(ass_to_html(ass), html, "{}", ass);
+        assert_eq!(html_to_ass(html), ass, "{}", html);
+    }
+}
+
+#[test]
+fn reads_other_ass_markup() {
+    // Several tags in one block, an alpha in the colour and tags without an SRT form
+    assert_eq!(ass_to_html("{\\an8\\i1\\1c&H00FF0000&}x{\\i0\\c}"), "<i><font color=\"#0000ff\">x</i></font>");
+    assert_eq!(ass_to_html("{\\clip(0,0,10,10)\\fs40}x"), "x");
+    assert_eq!(ass_to_html("{\\p1}m 0 0 l 10 10{\\p0}x\\hy"), "x\u{a0}y");
+    assert_eq!(html_to_ass("<I>x</I> <font face=\"Arial\">y</font> <span>z</span>"), "{\\i1}x{\\i0} y{\\c} z");
+}
+
+#[test]
+fn converts_line_breaks() {
+    let ass = format!("{}[Events]\nDialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,{{\\i1}}one\\Ntwo{{\\i0}}\n", subsync::convert::DEFAULT_ASS_HEADER);
+    let doc = SubtitleDocument::parse(&ass, SubtitleFormat::Ass);
+    let srt = doc.convert(SubtitleFormat::Srt, &Default::default()).serialize();
+    assert_eq!(srt, "1\n00:00:01,000 --> 00:00:02,000\n<i>one\ntwo</i>\n");
+    
+    let back = SubtitleDocument::parse(&srt, SubtitleFormat::Srt).convert(SubtitleFormat::Ass, &Default::default()).serialize();
+    assert!(back.ends_with("Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,{\\i1}one\\Ntwo{\\i0}\n"), "{}", back);
+}
+
+#[test]
+fn writes_the_default_ass_header() {
+    let doc = SubtitleDocument::parse("1\n00:00:01,000 --> 00:00:02,500\n<b>Hello</b>\n", SubtitleFormat::Srt);
+    let ass = doc.convert(SubtitleFormat::Ass, &Default::default()).serialize();
+    assert!(ass.starts_with("[Script Info]\nScriptType: v4.00+\n"), "{}", ass);
+    assert!(ass.contains("\n[V4+ Styles]\nFormat: Name, Fontname,"), "{}", ass);
+    assert!(ass.contains("\nStyle: Default,Arial,72,"), "{}", ass);
+    assert!(ass.ends_with("\n[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\nDialogue: 0,0:00:01.00,0:00:02.50,Default,,0,0,0,,{\\b1}Hello{\\b0}\n"), "{}", ass);
+    
+    // WebVTT keeps only the markup it has
+    let vtt = SubtitleDocument::parse(&ass, SubtitleFormat::Ass).convert(SubtitleFormat::Vtt, &Default::default()).serialize();
+    assert_eq!(vtt, "WEBVTT\n\n00:00:01.000 --> 00:00:02.500\n<b>Hello</b>\n");
+}
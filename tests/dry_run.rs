@@ -0,0 +1,75 @@
+// --dry-run shows the plan and leaves the folder as it was
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Every file and folder under `dir`, with the contents of the files.
+fn snapshot(dir: &Path) -> BTreeMap<PathBuf, Option<Vec<u8>>> {
+    let mut entries = BTreeMap::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            entries.extend(snapshot(&path));
+            entries.insert(path, None);
+        } else {
+            entries.insert(path.clone(), Some(fs::read(&path).unwrap()));
+        }
+    }
+    entries
+}
+
+#[test]
+fn shows_the_plan_and_changes_nothing() {
+    let dir = common::folder_with(
+        "dry-run",
+        &[
+            ("Show - 01.mkv", ""),
+            ("Show - 02.mkv", ""),
+            ("[Group] Show - 01.en.srt", "1\r\n00:00:01,000 --> 00:00:02,000\r\nHello\r\n"),
+            ("[Group] Show - 02.srt", "\u{feff}1\n00:00:03,500 --> 00:00:04,000\nWorld\n"),
+            ("notes.txt", "not a subtitle\n"),
+        ],
+    );
+    let before = snapshot(&dir);
+    let output = common::run_on(&dir, &["--dry-run", "--to", "vtt"], &["1.5"]);
+    assert!(output.status.success());
+    assert_eq!(snapshot(&dir), before);
+    assert!(!dir.join(".subsync").exists());
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for expected in [
+        "Dry run: no files will be changed",
+        "Processing: [Group] Show - 01.en.srt\n  Episode: 1\n  Video: Show - 01.mkv\n  Target: Show - 01.en.vtt\n",
+        "    00:00:01,000 --> 00:00:02,000  =>  00:00:02,500 --> 00:00:03,500\n",
+        "Processing: [Group] Show - 02.srt\n  Episode: 2\n  Video: Show - 02.mkv\n  Target: Show - 02.vtt\n",
+        "    00:00:03,500 --> 00:00:04,000  =>  00:00:05,000 --> 00:00:05,500\n",
+        "✓ Dry run complete, no files were changed",
+    ] {
+        assert!(stdout.contains(expected), "missing {:?} in\n{}", expected, stdout);
+    }
+    assert!(!stdout.contains("To restore"), "{}", stdout);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn shows_collisions_without_resolving_them() {
+    let dir = common::folder_with(
+        "dry-run-collision",
+        &[
+            ("Show - 01.mkv", ""),
+            ("[A] Show - 01.srt", "1\n00:00:01,000 --> 00:00:02,000\nA\n"),
+            ("[B] Show - 01.srt", "1\n00:00:01,000 --> 00:00:02,000\nB\n"),
+        ],
+    );
+    let before = snapshot(&dir);
+    let output = common::run_on(&dir, &["--dry-run"], &["1"]);
+    assert!(output.status.success());
+    assert_eq!(snapshot(&dir), before);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("  Target: Show - 01.2.srt\n"), "{}", stdout);
+    assert!(stdout.contains("Collisions (1):"), "{}", stdout);
+    fs::remove_dir_all(&dir).unwrap();
+}
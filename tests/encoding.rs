@@ -0,0 +1,101 @@
+// Legacy encodings: round trips through each table, detection and byte order marks
+
+use subsync::encoding::{detect, encode_text};
+use subsync::{DecodedText, Encoding};
+
+/// (encoding, text using its non-ASCII range)
+const SAMPLES: &[(Encoding, &str)] = &[
+    (Encoding::ShiftJis, "こんにちは、世界。ｶﾀｶﾅ　テスト"),
+    (Encoding::Gbk, "中文字幕测试，你好世界。"),
+    (Encoding::Windows1250, "Zażółć gęślą jaźń. Příliš žluťoučký kůň."),
+    (Encoding::Windows1251, "Съешь же ещё этих мягких французских булок."),
+    (Encoding::Windows1252, "Où est la bibliothèque ? Ça coûte 5 € — « très » cher."),
+    (Encoding::Windows1253, "Καλημέρα κόσμε, τι κάνεις;"),
+    (Encoding::Windows1254, "Pijamalı hasta yağız şoföre çabucak güvendi."),
+    (Encoding::Iso8859_1, "Grüße aus Köln, señor."),
+    (Encoding::Iso8859_2, "Zażółć gęślą jaźń."),
+    (Encoding::Iso8859_5, "Привет, мир."),
+    (Encoding::Iso8859_7, "Καλημέρα κόσμε."),
+    (Encoding::Iso8859_9, "Yağız şoför."),
+    (Encoding::Iso8859_15, "Cela coûte 5 €."),
+    (Encoding::Koi8R, "Съешь же ещё этих мягких французских булок."),
+];
+
+#[test]
+fn round_trips_every_encoding() {
+    for &(encoding, text) in SAMPLES {
+        let bytes = encoding.encode(text).unwrap_or_else(|c| panic!("{} cannot encode {:?}", encoding, c));
+        assert!(bytes.len() > text.chars().count() / 2, "{}", encoding);
+        assert_eq!(encoding.decode(&bytes).as_deref(), Some(text), "{}", encoding);
+    }
+}
+
+#[test]
+fn reports_unencodable_characters() {
+    assert_eq!(Encoding::Windows1252.encode("Привет"), Err('П'));
+    assert_eq!(Encoding::ShiftJis.encode("añ"), Err('ñ'));
+    assert_eq!(Encoding::Gbk.encode("中€"), Err('€'));
+}
+
+#[test]
+fn rejects_invalid_double_byte_sequences() {
+    assert_eq!(Encoding::ShiftJis.decode(b"\x82"), None);
+    assert_eq!(Encoding::Gbk.decode(b"\xC4\x20"), None);
+    assert_eq!(Encoding::Utf16Le.decode(b"a\x00b"), None);
+}
+
+/// (encoding, text) detected from the encoding's bytes alone
+const DETECTED: &[(Encoding, &str)] = &[
+    (Encoding::Windows1252, "Où est la bibliothèque ?"),
+    (Encoding::Windows1252, "Grüße aus Köln"),
+    (Encoding::Windows1252, "¿Qué pasó, señor? Él dijo que no había nada más que hacer allí."),
+    (Encoding::Windows1250, "Příliš žluťoučký kůň úpěl ďábelské ódy."),
+    (Encoding::Windows1250, "Zażółć gęślą jaźń"),
+    (Encoding::Windows1251, "Привет, мир!"),
+    (Encoding::Windows1251, "Съешь же ещё этих мягких французских булок, да выпей чаю."),
+    (Encoding::Koi8R, "Съешь же ещё этих мягких французских булок, да выпей чаю."),
+    (Encoding::Windows1253, "Καλημέρα κόσμε, τι κάνεις σήμερα;"),
+    (Encoding::ShiftJis, "こんにちは"),
+    (Encoding::ShiftJis, "今日はいい天気ですね。映画を見に行きましょう。"),
+    (Encoding::Gbk, "你好"),
+    (Encoding::Gbk, "中文字幕测试，你好世界"),
+    (Encoding::Gbk, "我们明天去看电影吧，听说这部电影非常好看，大家都很喜欢。"),
+];
+
+#[test]
+fn detects_short_and_long_samples() {
+    for &(encoding, text) in DETECTED {
+        let bytes = encoding.encode(text).unwrap();
+        assert_eq!(detect(&bytes), encoding, "{:?}", text);
+        for lines in [1, 20] {
+            let subtitle = format!("1\r\n00:00:01,000 --> 00:00:02,000\r\n{}\r\n\r\n", text).repeat(lines);
+            let decoded = DecodedText::decode(&encoding.encode(&subtitle).unwrap(), None).unwrap();
+            assert_eq!(decoded.encoding, encoding, "{:?} x{}", text, lines);
+            assert_eq!(decoded.text, subtitle);
+        }
+    }
+}
+
+#[test]
+fn detects_unicode() {
+    assert_eq!(detect(b"plain ASCII"), Encoding::Utf8);
+    assert_eq!(detect("Grüße".as_bytes()), Encoding::Utf8);
+    assert_eq!(detect(&Encoding::Utf16Le.encode("Hello, world").unwrap()), Encoding::Utf16Le);
+    assert_eq!(detect(&Encoding::Utf16Be.encode("Hello, world").unwrap()), Encoding::Utf16Be);
+}
+
+#[test]
+fn keeps_byte_order_marks() {
+    for encoding in [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be] {
+        for bom in [false, true] {
+            let bytes = encode_text("1\n00:00:01,000 --> 00:00:02,000\nCafé\n", encoding, bom).unwrap();
+            assert_eq!(bytes.starts_with(encoding.bom().unwrap()), bom, "{}", encoding);
+            
+            let decoded = DecodedText::decode(&bytes, None).unwrap();
+            assert_eq!(decoded, DecodedText { text: "1\n00:00:01,000 --> 00:00:02,000\nCafé\n".to_string(), encoding, bom });
+            assert_eq!(encode_text(&decoded.text, decoded.encoding, decoded.bom).unwrap(), bytes);
+        }
+    }
+    // Legacy encodings have no byte order mark to keep
+    assert_eq!(encode_text("Café", Encoding::Windows1252, true).unwrap(), b"Caf\xE9");
+}
@@ -0,0 +1,65 @@
+// Episode keys from season folders, and pairing subtitles with the nearest video
+
+use std::path::{Path, PathBuf};
+
+use subsync::episode::season_from_folder;
+use subsync::{episode_key, find_nearest_video, EpisodeKey};
+
+#[test]
+fn reads_season_folders() {
+    for (name, season) in [("Season 2", 2), ("season.02", 2), ("Series 10", 10), ("Saison 3", 3), ("Staffel_4", 4), ("S05", 5), (" S1 ", 1)] {
+        assert_eq!(season_from_folder(name), Some(season), "{}", name);
+    }
+    for name in ["Show", "Season", "Season 123", "Specials", "S01E02", "Show Season 2"] {
+        assert_eq!(season_from_folder(name), None, "{}", name);
+    }
+}
+
+#[test]
+fn takes_the_season_from_the_folder() {
+    let key = |path: &str| episode_key(Path::new(path));
+    assert_eq!(key("Show/Season 2/Show - 05.srt"), Some(EpisodeKey::new(Some(2), 5)));
+    assert_eq!(key("Season 2/Show - 05-06.srt"), Some(EpisodeKey::range(Some(2), 5, Some(6))));
+    // A season in the name wins over the folder's
+    assert_eq!(key("Season 2/Show S03E05.srt"), Some(EpisodeKey::new(Some(3), 5)));
+    assert_eq!(key("Extras/Show - 05.srt"), Some(EpisodeKey::new(None, 5)));
+    assert_eq!(key("Show - 05.srt"), Some(EpisodeKey::new(None, 5)));
+    assert_eq!(key("Season 2/notes.txt"), None);
+}
+
+fn videos(paths: &[(&str, EpisodeKey)]) -> Vec<(PathBuf, EpisodeKey)> {
+    paths.iter().map(|&(path, key)| (PathBuf::from(path), key)).collect()
+}
+
+#[test]
+fn pairs_with_the_nearest_video() {
+    let videos = videos(&[
+        ("Show/Season 1/Show - 05.mkv", EpisodeKey::new(Some(1), 5)),
+        ("Show/Season 2/Show - 05.mkv", EpisodeKey::new(Some(2), 5)),
+        ("Show/Show - 05.mkv", EpisodeKey::new(None, 5)),
+        ("Other/Show - 05.mkv", EpisodeKey::new(None, 5)),
+    ]);
+    let nearest = |key, folder: &str| find_nearest_video(&videos, key, Path::new(folder)).map(|path| path.to_str().unwrap());
+    
+    // An exact key picks its own season, however far away
+    assert_eq!(nearest(EpisodeKey::new(Some(2), 5), "Show/Season 1/Subs"), Some("Show/Season 2/Show - 05.mkv"));
+    // Without an exact key the nearest of the matching videos wins
+    assert_eq!(nearest(EpisodeKey::new(Some(3), 5), "Show/Subs"), Some("Show/Show - 05.mkv"));
+    assert_eq!(nearest(EpisodeKey::new(Some(3), 5), "Other/Subs"), Some("Other/Show - 05.mkv"));
+    // Exact keys without a season: the nearer of the two
+    assert_eq!(nearest(EpisodeKey::new(None, 5), "Show/Season 1"), Some("Show/Show - 05.mkv"));
+    assert_eq!(nearest(EpisodeKey::new(None, 6), "Show"), None);
+}
+
+#[test]
+fn refuses_a_tie_between_folders() {
+    let videos = videos(&[
+        ("Show/A/Show - 05.mkv", EpisodeKey::new(Some(1), 5)),
+        ("Show/B/Show - 05.mkv", EpisodeKey::new(Some(2), 5)),
+    ]);
+    assert_eq!(find_nearest_video(&videos, EpisodeKey::new(None, 5), Path::new("Show/Subs")), None);
+    assert_eq!(
+        find_nearest_video(&videos, EpisodeKey::new(None, 5), Path::new("Show/B/Subs")),
+        Some(&PathBuf::from("Show/B/Show - 05.mkv"))
+    );
+}
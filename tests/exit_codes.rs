@@ -0,0 +1,64 @@
+// Exit status of a run, and failed files not stopping the others
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+const CUE: &str = "1\n00:00:01,000 --> 00:00:02,000\nHello\n";
+
+/// A subtitle with a character Windows-1252 has no byte for.
+const CJK_CUE: &str = "1\n00:00:01,000 --> 00:00:02,000\n漢字\n";
+
+fn folder(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    common::folder_with(&format!("exit-{}", name), files)
+}
+
+#[test]
+fn succeeds_with_zero() {
+    let dir = folder("ok", &[("Show - 01.mkv", ""), ("Show - 01.en.srt", CUE)]);
+    let output = common::run_on(&dir, &[], &["1"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(dir.join("Show - 01.en.srt").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fails_with_one_when_every_file_fails() {
+    let dir = folder("all-failed", &[("Show - 01.srt", CJK_CUE), ("Show - 02.srt", CJK_CUE)]);
+    let output = common::run_on(&dir, &["--output-encoding", "windows-1252"], &["1"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(fs::read_to_string(dir.join("Show - 01.srt")).unwrap(), CJK_CUE);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Done, 2 of 2 files failed"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn carries_on_after_a_failed_file() {
+    let dir = folder("partial", &[("Show - 01.srt", CJK_CUE), ("Show - 02.srt", CUE), ("Show - 03.srt", CUE)]);
+    let output = common::run_on(&dir, &["--output-encoding", "windows-1252"], &["1"]);
+    assert_eq!(output.status.code(), Some(3));
+    // The failed file is left as it was, the ones after it are processed
+    assert_eq!(fs::read_to_string(dir.join("Show - 01.srt")).unwrap(), CJK_CUE);
+    assert!(!dir.join("shifted_Show - 01.srt").exists());
+    for name in ["shifted_Show - 02.srt", "shifted_Show - 03.srt"] {
+        assert_eq!(fs::read_to_string(dir.join(name)).unwrap(), "1\n00:00:02,000 --> 00:00:03,000\nHello\n");
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Failed (1):\n  Show - 01.srt: '漢' (U+6F22) cannot be written in windows-1252"), "{}", stdout);
+    assert!(stdout.contains("Done, 1 of 3 files failed"), "{}", stdout);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rejects_invalid_arguments_with_two() {
+    let dir = folder("usage", &[("Show - 01.srt", CUE)]);
+    assert_eq!(common::run_on(&dir, &["--no-such-option"], &["1"]).status.code(), Some(2));
+    assert_eq!(common::run_on(&dir, &[], &["one"]).status.code(), Some(2));
+    assert_eq!(common::run(&[]).status.code(), Some(2));
+    // A folder path that is a file, or nothing at all
+    assert_eq!(common::run_on(&dir.join("Show - 01.srt"), &[], &["1"]).status.code(), Some(2));
+    assert_eq!(common::run_on(&dir.join("missing"), &[], &["1"]).status.code(), Some(2));
+    assert_eq!(fs::read_to_string(dir.join("Show - 01.srt")).unwrap(), CUE);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,51 @@
+// Extension lists set in the environment, and the command-line options applied after them
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Output;
+
+fn folder(name: &str) -> PathBuf {
+    common::folder_with(
+        &format!("ext-{}", name),
+        &[("Show - 01.mkv", ""), ("[Group] Show - 01.srt", "1\n00:00:01,000 --> 00:00:02,000\nHi\n")],
+    )
+}
+
+fn run(env: &[(&str, &str)], args: &[&str]) -> Output {
+    common::command().envs(env.iter().copied()).args(args).output().unwrap()
+}
+
+#[test]
+fn environment_sets_extensions() {
+    let dir = folder("ignore");
+    let output = run(&[("SUBSYNC_IGNORE_EXT", "mkv")], &[dir.to_str().unwrap(), "1"]);
+    assert!(output.status.success());
+    assert!(dir.join("shifted_[Group] Show - 01.srt").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn options_apply_after_the_environment() {
+    let dir = folder("override");
+    let output = run(&[("SUBSYNC_IGNORE_EXT", ".MKV")], &["--video-ext", "mkv", dir.to_str().unwrap(), "1"]);
+    assert!(output.status.success());
+    assert!(dir.join("Show - 01.srt").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rejects_invalid_environment_lists() {
+    let output = run(&[("SUBSYNC_SUBTITLE_EXT", "txt=doc")], &["missing-folder", "1"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("SUBSYNC_SUBTITLE_EXT"));
+}
+
+#[test]
+fn help_lists_configured_extensions() {
+    let output = run(&[("SUBSYNC_VIDEO_EXT", "3gp"), ("SUBSYNC_SUBTITLE_EXT", "ssa=ass")], &["--help"]);
+    let help = String::from_utf8_lossy(&output.stderr);
+    assert!(help.lines().any(|line| line.starts_with("  Videos:") && line.ends_with(", 3gp")), "{}", help);
+    assert!(help.contains("  Subtitles: srt, ass, vtt, ssa"), "{}", help);
+}
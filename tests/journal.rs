@@ -0,0 +1,133 @@
+// Undo journal: recording a run, undoing it and the conflicts that stop an undo
+
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use subsync::journal::{list_runs, read_journal, undo, JournalEntry, JOURNAL_DIR};
+use subsync::Journal;
+
+fn folder(name: &str) -> PathBuf {
+    common::folder(&format!("journal-{}", name))
+}
+
+fn read(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok()
+}
+
+#[test]
+fn undoes_writes_replacements_and_removals() {
+    let root = folder("undo");
+    fs::write(root.join("ep01.srt"), "old subtitle").unwrap();
+    fs::write(root.join("ep01.mkv.srt"), "old target").unwrap();
+    
+    let mut journal = Journal::create(&root).unwrap();
+    journal.write_file(&root.join("ep01.mkv.srt"), b"new target").unwrap();
+    journal.write_file(&root.join("ep02.mkv.srt"), b"created").unwrap();
+    journal.remove_file(&root.join("ep01.srt")).unwrap();
+    let run_id = journal.run_id().to_string();
+    drop(journal);
+    assert_eq!(list_runs(&root), [run_id.as_str()]);
+    
+    let report = undo(&root, None).unwrap();
+    assert_eq!(report.run_id, run_id);
+    assert!(report.conflicts.is_empty());
+    assert_eq!(report.deleted, [PathBuf::from("ep02.mkv.srt"), PathBuf::from("ep01.mkv.srt")]);
+    assert_eq!(report.restored, [PathBuf::from("ep01.srt"), PathBuf::from("ep01.mkv.srt")]);
+    assert_eq!(read(&root.join("ep01.srt")).as_deref(), Some("old subtitle"));
+    assert_eq!(read(&root.join("ep01.mkv.srt")).as_deref(), Some("old target"));
+    assert!(!root.join("ep02.mkv.srt").exists());
+    assert!(list_runs(&root).is_empty());
+    
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn keeps_files_changed_after_the_run() {
+    let root = folder("conflicts");
+    fs::write(root.join("removed.srt"), "original").unwrap();
+    
+    let mut journal = Journal::create(&root).unwrap();
+    journal.write_file(&root.join("written.srt"), b"written").unwrap();
+    journal.remove_file(&root.join("removed.srt")).unwrap();
+    let run_id = journal.run_id().to_string();
+    drop(journal);
+    fs::write(root.join("written.srt"), "edited by hand").unwrap();
+    fs::write(root.join("removed.srt"), "new file").unwrap();
+    
+    let report = undo(&root, Some(&run_id)).unwrap();
+    assert_eq!(
+        report.conflicts,
+        ["removed.srt exists, not restoring the backup over it", "written.srt was modified after the run, kept it"]
+    );
+    assert!(report.deleted.is_empty() && report.restored.is_empty());
+    assert_eq!(read(&root.join("written.srt")).as_deref(), Some("edited by hand"));
+    assert_eq!(read(&root.join("removed.srt")).as_deref(), Some("new file"));
+    assert_eq!(list_runs(&root), [run_id.as_str()]);
+    
+    // Once the conflicts are cleared, undoing again finishes the job
+    fs::remove_file(root.join("removed.srt")).unwrap();
+    let report = undo(&root, Some(&run_id)).unwrap();
+    assert_eq!(report.conflicts, ["written.srt was modified after the run, kept it"]);
+    assert_eq!(report.restored, [PathBuf::from("removed.srt")]);
+    assert_eq!(read(&root.join("removed.srt")).as_deref(), Some("original"));
+    
+    fs::write(root.join("written.srt"), "written").unwrap();
+    let report = undo(&root, Some(&run_id)).unwrap();
+    assert!(report.conflicts.is_empty());
+    assert_eq!(report.deleted, [PathBuf::from("written.srt")]);
+    assert!(report.restored.is_empty());
+    assert!(list_runs(&root).is_empty());
+    
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn escapes_tabs_and_newlines_in_paths() {
+    let root = folder("escape");
+    let name = "odd\tname\\with\nbreak.srt";
+    
+    let mut journal = Journal::create(&root).unwrap();
+    journal.write_file(&root.join(name), b"content").unwrap();
+    let run_id = journal.run_id().to_string();
+    drop(journal);
+    
+    let entries = read_journal(&root, &run_id).unwrap();
+    assert!(matches!(entries.as_slice(), [JournalEntry::Created { path, .. }] if path == Path::new(name)));
+    let report = undo(&root, None).unwrap();
+    assert_eq!(report.deleted, [PathBuf::from(name)]);
+    assert!(!root.join(name).exists());
+    
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn orders_runs_of_the_same_second() {
+    let root = folder("order");
+    for run in ["20260101-120000-10", "20260101-120000-2", "20260101-120000", "20251231-235959"] {
+        let dir = root.join(JOURNAL_DIR).join(run);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("journal"), "subsync-journal 1\n").unwrap();
+    }
+    assert_eq!(list_runs(&root), ["20251231-235959", "20260101-120000", "20260101-120000-2", "20260101-120000-10"]);
+    assert_eq!(undo(&root, None).unwrap().run_id, "20260101-120000-10");
+    
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn rejects_unknown_run_ids() {
+    let root = folder("reject");
+    let outside = folder("reject-outside");
+    fs::write(outside.join("journal"), "subsync-journal 1\n").unwrap();
+    
+    Journal::create(&root).unwrap();
+    let escape = format!("../../{}", outside.file_name().unwrap().to_string_lossy());
+    assert!(undo(&root, Some(&escape)).is_err());
+    assert!(undo(&root, Some("missing")).is_err());
+    assert!(outside.join("journal").exists());
+    
+    fs::remove_dir_all(&root).unwrap();
+    fs::remove_dir_all(&outside).unwrap();
+}
@@ -0,0 +1,87 @@
+// Lenient SRT parsing: repaired timing lines and the warnings for them
+
+use subsync::srt::parse_with_warnings;
+use subsync::{shift_srt, LineIssue, SubtitleFormat};
+
+/// (timing line, start, end) of variants found in real files
+const TIMINGS: &[(&str, i64, i64)] = &[
+    ("00:00:01.500 --> 00:00:02.000", 1500, 2000),
+    ("0:00:01,5 --> 0:0:2,25", 1500, 2250),
+    ("00:00:01,500-->00:00:02,000", 1500, 2000),
+    ("00:00:01,500  -->  00:00:02,000", 1500, 2000),
+    ("00:00:01,500 -> 00:00:02,000", 1500, 2000),
+    ("00:00:01:500 --> 00:00:02:000", 1500, 2000),
+    ("00:00:01 --> 00:00:02", 1000, 2000),
+    ("00:00:01,5001 --> 00:00:02,0009", 1500, 2000),
+    ("  00:00:01,500 --> 00:00:02,000", 1500, 2000),
+];
+
+#[test]
+fn repairs_timing_lines() {
+    for &(timing, start, end) in TIMINGS {
+        let (doc, warnings) = parse_with_warnings(&format!("1\n{}\nHello\n", timing));
+        let cue = doc.cues().next().unwrap_or_else(|| panic!("no cue read from '{}'", timing));
+        assert_eq!((cue.start, cue.end), (start, end), "{}", timing);
+        assert_eq!(warnings.len(), 1, "{}", timing);
+        assert_eq!((warnings[0].line, warnings[0].issue), (2, LineIssue::Repaired), "{}", timing);
+    }
+}
+
+#[test]
+fn normalizes_repaired_lines() {
+    let content = "1\r\n00:00:01.500-->00:00:02,000\r\nHello\r\n";
+    assert_eq!(shift_srt(content, 0), "1\r\n00:00:01,500 --> 00:00:02,000\r\nHello\r\n");
+    
+    // A `\r` left from a CRLF line in a file of LF lines
+    let content = "1\n00:00:01,500 --> 00:00:02,000\r\nHello\n";
+    let (doc, warnings) = parse_with_warnings(content);
+    assert_eq!(doc.cues().count(), 1);
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn reports_unparsed_lines() {
+    let content = "1\n00:00:01,000 --> 00:00:02,000\nHello\n\n2\n00:00:03,000 --> 00:00:0x,000\nBroken\n\nstray\n\n3\n00:00:05,000 --> 00:00:06,000\nWorld -> there\n";
+    let (doc, warnings) = parse_with_warnings(content);
+    assert_eq!(doc.cues().count(), 2);
+    let found: Vec<(usize, LineIssue)> = warnings.iter().map(|warning| (warning.line, warning.issue)).collect();
+    assert_eq!(found, [(6, LineIssue::InvalidTiming), (9, LineIssue::Stray)]);
+    assert_eq!(shift_srt(content, 0), content);
+}
+
+#[test]
+fn reports_an_unreadable_cue_before_the_next_counter() {
+    // No blank line between the broken cue and the next one
+    let content = "1\n00:00:01,000 --> xx\nBroken\n2\n00:00:03,000 --> 00:00:04,000\nFine\n";
+    let (doc, warnings) = parse_with_warnings(content);
+    assert_eq!(doc.cues().count(), 1);
+    let found: Vec<(usize, LineIssue)> = warnings.iter().map(|warning| (warning.line, warning.issue)).collect();
+    assert_eq!(found, [(2, LineIssue::InvalidTiming)]);
+    assert_eq!(shift_srt(content, 0), content);
+}
+
+#[test]
+fn reports_out_of_range_timings() {
+    let content = "1\n9999999999999:00:00,000 --> 9999999999999:00:01,000\nHuge\n\n2\n00:00:01,000 --> 00:00:02,000\nFine\n";
+    let (doc, warnings) = parse_with_warnings(content);
+    assert_eq!(doc.cues().count(), 1);
+    let found: Vec<(usize, LineIssue)> = warnings.iter().map(|warning| (warning.line, warning.issue)).collect();
+    assert_eq!(found, [(2, LineIssue::InvalidTiming)]);
+    assert_eq!(shift_srt(content, 1000), content.replace("00:00:01,000 --> 00:00:02,000", "00:00:02,000 --> 00:00:03,000"));
+    
+    // The largest hours that still fit are read, and shifting them saturates
+    let content = "1\n2562047788015:00:00,000 --> 2562047788015:00:01,000\nLate\n";
+    assert_eq!(parse_with_warnings(content).0.cues().count(), 1);
+    shift_srt(content, i64::MAX);
+}
+
+#[test]
+fn keeps_arrows_in_cue_text() {
+    let content = "1\n00:00:01,000 --> 00:00:02,000\nHe said --> go\nthen 5 -> 6\n\n2\n00:00:03,000 --> 00:00:04,000\nNext\n";
+    let (doc, warnings) = parse_with_warnings(content);
+    assert!(warnings.is_empty(), "{:?}", warnings);
+    let texts: Vec<&str> = doc.cues().map(|cue| cue.text.as_str()).collect();
+    assert_eq!(texts, ["He said --> go\nthen 5 -> 6", "Next"]);
+    let vtt = doc.convert(SubtitleFormat::Vtt, &Default::default()).serialize();
+    assert_eq!(vtt, "WEBVTT\n\n00:00:01.000 --> 00:00:02.000\nHe said --> go\nthen 5 -> 6\n\n00:00:03.000 --> 00:00:04.000\nNext\n");
+}
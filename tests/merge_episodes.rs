@@ -0,0 +1,38 @@
+// Runs the binary on a double-episode folder and checks the merged timestamps
+
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn episode_folder(name: &str) -> PathBuf {
+    common::folder_with(
+        name,
+        &[
+            ("Show - 01-02.mkv", ""),
+            ("Show - 01.srt", "1\n00:19:58,000 --> 00:20:01,000\nEnd one\n"),
+            ("Show - 02.srt", "1\n00:00:01,000 --> 00:00:02,000\nStart two\n"),
+        ],
+    )
+}
+
+fn run(dir: &Path, args: &[&str]) -> String {
+    assert!(common::run_on(dir, args, &["10"]).status.success());
+    let merged = fs::read_to_string(dir.join("Show - 01-02.srt")).unwrap();
+    fs::remove_dir_all(dir).unwrap();
+    merged
+}
+
+#[test]
+fn merged_parts_are_shifted_once() {
+    let dir = episode_folder("merge");
+    let merged = run(&dir, &["--merge-episodes"]);
+    assert_eq!(merged, "1\n00:20:08,000 --> 00:20:11,000\nEnd one\n\n2\n00:20:12,000 --> 00:20:13,000\nStart two\n");
+}
+
+#[test]
+fn episode_duration_sets_the_merge_offset() {
+    let dir = episode_folder("duration");
+    let merged = run(&dir, &["--merge-episodes", "--episode-duration", "00:22:00,000"]);
+    assert_eq!(merged, "1\n00:20:08,000 --> 00:20:11,000\nEnd one\n\n2\n00:22:11,000 --> 00:22:12,000\nStart two\n");
+}
@@ -0,0 +1,59 @@
+// Movie title similarity, and matching movie subtitles to videos by it
+
+use std::path::{Path, PathBuf};
+
+use subsync::movie::title_similarity;
+use subsync::{find_movie_video, movie_similarity};
+
+#[test]
+fn compares_letter_pairs() {
+    assert_eq!(title_similarity("night", "night"), 1.0);
+    // ni ig gh ht / na ac ch ht: one pair of eight shared
+    assert_eq!(title_similarity("night", "nacht"), 0.25);
+    // Repeated pairs only count as often as both titles have them
+    assert_eq!(title_similarity("aaaa", "aa"), 0.5);
+    assert_eq!(title_similarity("a", "ab"), 0.0);
+    assert_eq!(title_similarity("", "night"), 0.0);
+    assert_eq!(title_similarity("", ""), 0.0);
+}
+
+#[test]
+fn compares_movie_titles() {
+    // A clear match: the same title and year under different release names
+    assert_eq!(movie_similarity("The.Matrix.1999.1080p.BluRay.x264.en.srt", "The Matrix (1999).mkv"), 1.0);
+    assert_eq!(movie_similarity("Spiderman.2002.srt", "Spider-Man (2002).mkv"), 1.0);
+    // Near misses either side of the 0.6 threshold: 16/24 and 16/27 of the pairs shared
+    assert!((movie_similarity("The Matrix Reloaded.srt", "The Matrix.mkv") - 2.0 / 3.0).abs() < 1e-9);
+    assert!((movie_similarity("The Matrix Revolutions.srt", "The Matrix.mkv") - 16.0 / 27.0).abs() < 1e-9);
+    // No match: unrelated titles, and remakes with another year
+    assert!(movie_similarity("Heat.1995.srt", "Casablanca.1942.mkv") < 0.2);
+    assert_eq!(movie_similarity("Dune.1984.srt", "Dune.2021.mkv"), 0.0);
+    assert_eq!(movie_similarity("Dune.srt", "Dune.2021.mkv"), 1.0);
+    // Names with no title left once the tags are gone are not the same movie
+    assert_eq!(movie_similarity("[Group] [1080p].mkv", "[Other] [720p].srt"), 0.0);
+}
+
+fn find<'a>(videos: &'a [PathBuf], subtitle: &str) -> Option<&'a str> {
+    find_movie_video(videos, Path::new(subtitle)).map(|path| path.to_str().unwrap())
+}
+
+#[test]
+fn matches_by_title() {
+    let videos: Vec<PathBuf> = ["Movies/The Matrix (1999).mkv", "Movies/Heat (1995).mkv", "Movies/Dune (2021).mkv"].map(PathBuf::from).into();
+    assert_eq!(find(&videos, "Movies/The.Matrix.1999.en.srt"), Some("Movies/The Matrix (1999).mkv"));
+    assert_eq!(find(&videos, "Movies/Subs/Heat.1995.BluRay.srt"), Some("Movies/Heat (1995).mkv"));
+    assert_eq!(find(&videos, "Movies/The.Matrix.Reloaded.srt"), Some("Movies/The Matrix (1999).mkv"));
+    // Below the threshold and no nearest video either
+    assert_eq!(find(&videos, "Movies/The.Matrix.Revolutions.srt"), None);
+    assert_eq!(find(&videos, "Movies/Casablanca.1942.srt"), None);
+    assert_eq!(find(&videos, "Movies/Dune.1984.srt"), None);
+}
+
+#[test]
+fn falls_back_to_the_nearest_video() {
+    let videos: Vec<PathBuf> = ["Heat (1995)/Heat.mkv", "Dune (2021)/Dune.mkv"].map(PathBuf::from).into();
+    assert_eq!(find(&videos, "Heat (1995)/English.srt"), Some("Heat (1995)/Heat.mkv"));
+    assert_eq!(find(&videos, "Dune (2021)/Subs/movie.en.srt"), Some("Dune (2021)/Dune.mkv"));
+    assert_eq!(find(&videos, "Subs/English.srt"), None);
+    assert_eq!(find(&videos[..1], "Subs/English.srt"), Some("Heat (1995)/Heat.mkv"));
+}
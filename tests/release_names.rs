@@ -0,0 +1,139 @@
+// Release-name parsing against a corpus of real-world file names
+
+use subsync::ReleaseInfo;
+
+/// (file name, title, release group, season, episode, last episode)
+#[allow(clippy::type_complexity)]
+const CORPUS: &[(&str, Option<&str>, Option<&str>, Option<u32>, Option<u32>, Option<u32>)] = &[
+    // Fansub style
+    ("[SubsPlease] Spy x Family - 05 (1080p) [ABCD1234].mkv", Some("Spy x Family"), Some("SubsPlease"), None, Some(5), None),
+    ("[Erai-raws] Shingeki no Kyojin - The Final Season - 01 [1080p][Multiple Subtitle].mkv", Some("Shingeki no Kyojin - The Final Season"), Some("Erai-raws"), None, Some(1), None),
+    ("[HorribleSubs] Boruto - Naruto Next Generations - 100 [720p].mkv", Some("Boruto - Naruto Next Generations"), Some("HorribleSubs"), None, Some(100), None),
+    ("[Judas] Kimetsu no Yaiba (Season 2) - 01v2 [1080p][HEVC x265 10bit][Multi-Subs].mkv", Some("Kimetsu no Yaiba"), Some("Judas"), Some(2), Some(1), None),
+    ("[Anime Time] Dragon Ball Z Kai - 001.jpn.ass", Some("Dragon Ball Z Kai"), Some("Anime Time"), None, Some(1), None),
+    ("[Group] 86 - 05 [1080p].mkv", Some("86"), Some("Group"), None, Some(5), None),
+    ("[Group] 86 - Eighty Six - 12 [BD 1080p].mkv", Some("86 - Eighty Six"), Some("Group"), None, Some(12), None),
+    ("[Coalgirls] Steins;Gate 0 - 03 (1920x1080 Blu-ray FLAC) [DEADBEEF].mkv", Some("Steins;Gate 0"), Some("Coalgirls"), None, Some(3), None),
+    ("[Group] Mob Psycho 100 III - 01 [1080p].mkv", Some("Mob Psycho 100 III"), Some("Group"), None, Some(1), None),
+    ("[Group] Mob Psycho 100 - 07.mkv", Some("Mob Psycho 100"), Some("Group"), None, Some(7), None),
+    ("[Group] One Piece - 1071 [1080p].mkv", Some("One Piece"), Some("Group"), None, Some(1071), None),
+    ("[Group] Title - 12v3 [720p][0A1B2C3D].mkv", Some("Title"), Some("Group"), None, Some(12), None),
+    ("[Group] Title 05 [1080p].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title [05][1080p].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title (2019) - 05 [1080p].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title S2 - 05 [1080p].mkv", Some("Title"), Some("Group"), Some(2), Some(5), None),
+    ("[Group] Title 2nd Season - 05 [1080p].mkv", Some("Title"), Some("Group"), Some(2), Some(5), None),
+    ("[Group] Title Season 3 - 11 [1080p].mkv", Some("Title"), Some("Group"), Some(3), Some(11), None),
+    ("[Group] Title - 01-02 [1080p].mkv", Some("Title"), Some("Group"), None, Some(1), Some(2)),
+    ("[Group] Title - 01~03 [1080p].mkv", Some("Title"), Some("Group"), None, Some(1), Some(3)),
+    ("[Group] Title - Episode 05 [1080p].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title - Ep 05 [1080p].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title #05 [1080p].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title - 05 - The Beginning [1080p].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title - 05 (BD 1080p HEVC FLAC) [ABCDEF12].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title - 05 [Dual Audio][1080p].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title - 05 [x264 AAC][2E4A6C8B].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group]Title_-_05_[1080p].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title_05_[720p].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title - 05 [1080p] [12345678].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("【Group】Title - 05【1080p】.mp4", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title - 005.ass", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title - 05.en.forced.srt", Some("Title"), Some("Group"), None, Some(5), None),
+    ("(Group) Title - 05 [1080p].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Re Zero kara Hajimeru Isekai Seikatsu - 01 [BD 1080p x264 10bit FLAC].mkv", Some("Re Zero kara Hajimeru Isekai Seikatsu"), Some("Group"), None, Some(1), None),
+    ("[Group] Kaguya-sama wa Kokurasetai - 03 [1080p].mkv", Some("Kaguya-sama wa Kokurasetai"), Some("Group"), None, Some(3), None),
+    ("[Group] Fate Zero - 25 [BD 720p AAC].mkv", Some("Fate Zero"), Some("Group"), None, Some(25), None),
+    ("[Group] Gintama - 201 [720p][AAC 2.0].mkv", Some("Gintama"), Some("Group"), None, Some(201), None),
+    ("[Group] Title - 05 (WEB 1080p AAC 2.0) [F00DCAFE].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title - 05 (Hi10P 720p).mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title - 05 [4K HDR].mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title - 05 [1080p] (uncensored).mkv", Some("Title"), Some("Group"), None, Some(5), None),
+    ("[Group] Title S01E05 [1080p].mkv", Some("Title"), Some("Group"), Some(1), Some(5), None),
+    // Scene and TV style
+    ("Dragon.Ball.Z.Kai.E01.MULTi.1080p.BluRay.x265-KHAYA.mkv", Some("Dragon Ball Z Kai"), Some("KHAYA"), None, Some(1), None),
+    ("Show.S01E05.1080p.WEB-DL.DDP5.1.H.264-NTb.mkv", Some("Show"), Some("NTb"), Some(1), Some(5), None),
+    ("Show.Name.S02E10.720p.HDTV.x264-LOL.mkv", Some("Show Name"), Some("LOL"), Some(2), Some(10), None),
+    ("Show.Name.2019.S01E03.1080p.AMZN.WEB-DL.DDP5.1.H.264-NTG.mkv", Some("Show Name"), Some("NTG"), Some(1), Some(3), None),
+    ("Show.Name.S01E01-E02.1080p.mkv", Some("Show Name"), None, Some(1), Some(1), Some(2)),
+    ("Show.Name.S01E01E02.720p.mkv", Some("Show Name"), None, Some(1), Some(1), Some(2)),
+    ("Show.Name.S01E01-02.mkv", Some("Show Name"), None, Some(1), Some(1), Some(2)),
+    ("Show.Name.S01.E05.mkv", Some("Show Name"), None, Some(1), Some(5), None),
+    ("Show.S01E05.1080p.mkv", Some("Show"), None, Some(1), Some(5), None),
+    ("Show.S01E05v2.1080p.mkv", Some("Show"), None, Some(1), Some(5), None),
+    ("show.s03e07.srt", Some("show"), None, Some(3), Some(7), None),
+    ("Show.Name.1x05.HDTV.XviD-GROUP.avi", Some("Show Name"), Some("GROUP"), Some(1), Some(5), None),
+    ("Show Name 1x05.srt", Some("Show Name"), None, Some(1), Some(5), None),
+    ("Show Name 1x01-1x02.mkv", Some("Show Name"), None, Some(1), Some(1), Some(2)),
+    ("Show Name - 2x11 - Episode Title.mkv", Some("Show Name"), None, Some(2), Some(11), None),
+    ("Show Name - S01E05 - Episode Title.mkv", Some("Show Name"), None, Some(1), Some(5), None),
+    ("Show Name S01E05 Episode Title 1080p.mkv", Some("Show Name"), None, Some(1), Some(5), None),
+    ("Show.Name.S01E05.Episode.Title.1080p.WEBRip.x265-RARBG.mp4", Some("Show Name"), Some("RARBG"), Some(1), Some(5), None),
+    ("Show Name Season 1 Episode 5.mkv", Some("Show Name"), None, Some(1), Some(5), None),
+    ("Show.Name.Ep.05.720p.mkv", Some("Show Name"), None, None, Some(5), None),
+    ("Show.Name.EP05.mkv", Some("Show Name"), None, None, Some(5), None),
+    ("Show_Name_EP05_1080p.mkv", Some("Show Name"), None, None, Some(5), None),
+    ("The.Show.1920x1080.E07.mkv", Some("The Show"), None, None, Some(7), None),
+    ("Title.2049.S01E02.mkv", Some("Title"), None, Some(1), Some(2), None),
+    // Short and plain names
+    ("ep01.srt", None, None, None, Some(1), None),
+    ("ep01.en.srt", None, None, None, Some(1), None),
+    ("Episode 12.srt", None, None, None, Some(12), None),
+    ("episode01.ass", None, None, None, Some(1), None),
+    ("episode_01.ass", None, None, None, Some(1), None),
+    ("E05.mkv", None, None, None, Some(5), None),
+    ("Show - 01.srt", Some("Show"), None, None, Some(1), None),
+    ("Show - 01-02.mkv", Some("Show"), None, None, Some(1), Some(2)),
+    ("Show 07.srt", Some("Show"), None, None, Some(7), None),
+    ("Show_07.srt", Some("Show"), None, None, Some(7), None),
+    ("Show.07.srt", Some("Show"), None, None, Some(7), None),
+    ("Show - 01.pt-BR.srt", Some("Show"), None, None, Some(1), None),
+    ("Show - 01.jpn.sdh.ass", Some("Show"), None, None, Some(1), None),
+    // No episode at all
+    ("Movie Title (2019) [1080p].mkv", Some("Movie Title"), None, None, None, None),
+    ("Movie.Title.2019.1080p.BluRay.x264-GROUP.mkv", Some("Movie Title"), Some("GROUP"), None, None, None),
+    ("[Group] Movie Title [BD 1080p].mkv", Some("Movie Title"), Some("Group"), None, None, None),
+    ("The.Movie.It.srt", Some("The Movie It"), None, None, None, None),
+    ("Movie.Title.2019.It.srt", Some("Movie Title"), None, None, None, None),
+];
+
+#[test]
+fn corpus() {
+    let mut failures = Vec::new();
+    for &(name, title, group, season, episode, last_episode) in CORPUS {
+        let info = ReleaseInfo::parse(name);
+        let actual = (info.title.as_deref(), info.release_group.as_deref(), info.season, info.episode, info.last_episode);
+        if actual != (title, group, season, episode, last_episode) {
+            failures.push(format!(
+                "{}\n    expected {:?}\n    got      {:?}",
+                name,
+                (title, group, season, episode, last_episode),
+                actual
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{} of {} names misparsed:\n{}", failures.len(), CORPUS.len(), failures.join("\n"));
+}
+
+#[test]
+fn metadata() {
+    let info = ReleaseInfo::parse("[Judas] Kimetsu no Yaiba (Season 2) - 01v2 [1080p][HEVC x265 10bit][Multi-Subs].mkv");
+    assert_eq!(info.version, Some(2));
+    assert_eq!(info.resolution.as_deref(), Some("1080p"));
+    assert_eq!(info.video_terms, ["HEVC", "x265", "10bit"]);
+    assert_eq!(info.extension.as_deref(), Some("mkv"));
+    
+    let info = ReleaseInfo::parse("[Coalgirls] Steins;Gate 0 - 03 (1920x1080 Blu-ray FLAC) [DEADBEEF].mkv");
+    assert_eq!(info.crc.as_deref(), Some("DEADBEEF"));
+    assert_eq!(info.resolution.as_deref(), Some("1920x1080"));
+    assert_eq!(info.source.as_deref(), Some("Blu-ray"));
+    assert_eq!(info.audio_terms, ["FLAC"]);
+    
+    let info = ReleaseInfo::parse("Show.Name.2019.S01E03.1080p.AMZN.WEB-DL.DDP5.1.H.264-NTG.mkv");
+    assert_eq!(info.year, Some(2019));
+    assert_eq!(info.source.as_deref(), Some("WEB-DL"));
+    assert_eq!(info.video_terms, ["H264"]);
+    assert_eq!(info.audio_terms, ["DDP51"]);
+    
+    let info = ReleaseInfo::parse("[Group] Title - 12 v2 [720p].mkv");
+    assert_eq!((info.episode, info.version), (Some(12), Some(2)));
+}
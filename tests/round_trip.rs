@@ -0,0 +1,99 @@
+// Parsing and serializing with a zero shift must give back the original bytes
+
+use subsync::{shift_ass, shift_srt, shift_vtt, CueMeta, SrtCoordinates, SubtitleDocument, SubtitleFormat};
+
+const SRT: &[&str] = &[
+    "1\n00:00:01,000 --> 00:00:02,500\nHello\n\n2\n00:00:03,000 --> 00:00:04,000\nWorld\n",
+    "1\r\n00:00:01,000 --> 00:00:02,500\r\nHello\r\n\r\n2\r\n00:00:03,000 --> 00:00:04,000\r\nWorld\r\n\r\n",
+    "\u{FEFF}1\r\n00:00:01,000 --> 00:00:02,500\r\nHello\r\nthere\r\n",
+    // No final newline, with and without CRLF
+    "1\n00:00:01,000 --> 00:00:02,500\nHello",
+    "1\r\n00:00:01,000 --> 00:00:02,500\r\nHello",
+    "1\r\n00:00:01,000 --> 00:00:02,500\r\nHello\r",
+    // Mixed line endings
+    "1\r\n00:00:01,000 --> 00:00:02,500\r\nHello\n\n2\n00:00:03,000 --> 00:00:04,000\r\nWorld\r\n",
+    // Trailing whitespace after the timing
+    "1\n00:00:01,000 --> 00:00:02,500 \t\nHello\n",
+    // Trailing whitespace and extra blank lines
+    "1\n00:00:01,000 --> 00:00:02,500\nHello  \n\n\n\n2\n00:00:03,000 --> 00:00:04,000\n  World\t\n\n\n",
+    // Coordinates after the timing
+    "1\n00:00:01,000 --> 00:00:02,500 X1:100 X2:500 Y1:400 Y2:450\nHello\n",
+    "",
+    "\n",
+];
+
+const ASS: &[&str] = &[
+    "[Script Info]\r\nScriptType: v4.00+\r\n\r\n[Events]\r\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\r\nDialogue: 0,0:00:01.00,0:00:02.50,Default,,0,0,0,,Hello\\NWorld\r\n",
+    "\u{FEFF}[Events]\nDialogue: 0,00:00:01.00,0:00:02.5,Default,,0000,0000,0000,,Hello",
+];
+
+const VTT: &[&str] = &[
+    "WEBVTT\n\n00:01.000 --> 00:02.500\nHello\n\nid\n00:00:03.000 --> 00:00:04.000   line:0  position:10%\nWorld\n",
+    "\u{FEFF}WEBVTT\r\n\r\nNOTE kept\r\n\r\n00:00:01.000 --> 00:00:02.500\r\nHello\r\n",
+];
+
+#[test]
+fn srt_round_trips() {
+    for &content in SRT {
+        assert_eq!(shift_srt(content, 0), content);
+    }
+}
+
+#[test]
+fn ass_round_trips() {
+    for &content in ASS {
+        assert_eq!(shift_ass(content, 0), content);
+    }
+}
+
+#[test]
+fn vtt_round_trips() {
+    for &content in VTT {
+        assert_eq!(shift_vtt(content, 0), content);
+    }
+}
+
+#[test]
+fn shifting_keeps_untouched_lines() {
+    let content = "\u{FEFF}1\r\n00:00:01,000 --> 00:00:02,500 \r\nHello  \r\n\r\n";
+    assert_eq!(shift_srt(content, 1000), "\u{FEFF}1\r\n00:00:02,000 --> 00:00:03,500\r\nHello  \r\n\r\n");
+    
+    let content = "WEBVTT\n\n00:01.000 --> 00:02.500 line:0\nHello";
+    assert_eq!(shift_vtt(content, -500), "WEBVTT\n\n00:00:00.500 --> 00:00:02.000 line:0\nHello");
+}
+
+#[test]
+fn vtt_header_is_not_a_cue_identifier() {
+    let content = "WEBVTT\n00:00:01.000 --> 00:00:02.500\nHello\n";
+    let doc = SubtitleDocument::parse(content, SubtitleFormat::Vtt);
+    let cue = doc.cues().next().unwrap();
+    assert_eq!(cue.meta, CueMeta::Vtt { identifier: None, settings: String::new() });
+    assert_eq!(doc.serialize(), content);
+    assert_eq!(shift_vtt(content, 1000), "WEBVTT\n00:00:02.000 --> 00:00:03.500\nHello\n");
+    
+    // Without a header, the line before the first cue is still its identifier
+    let doc = SubtitleDocument::parse("intro\n00:00:01.000 --> 00:00:02.500\nHello\n", SubtitleFormat::Vtt);
+    let cue = doc.cues().next().unwrap();
+    assert_eq!(cue.meta, CueMeta::Vtt { identifier: Some("intro".to_string()), settings: String::new() });
+}
+
+#[test]
+fn conversion_keeps_line_endings() {
+    let doc = SubtitleDocument::parse("1\r\n00:00:01,000 --> 00:00:02,500\r\nHello\r\n", SubtitleFormat::Srt);
+    let vtt = doc.convert(SubtitleFormat::Vtt, &Default::default()).serialize();
+    assert_eq!(vtt, "WEBVTT\r\n\r\n00:00:01.000 --> 00:00:02.500\r\nHello\r\n");
+}
+
+#[test]
+fn coordinates_are_kept() {
+    let content = "1\n00:00:01,000 --> 00:00:02,500  X1:100 X2:500 Y1:400 Y2:450\nHello\n";
+    assert_eq!(shift_srt(content, 1000), "1\n00:00:02,000 --> 00:00:03,500 X1:100 X2:500 Y1:400 Y2:450\nHello\n");
+    
+    let doc = SubtitleDocument::parse(content, SubtitleFormat::Srt);
+    let cue = doc.cues().next().unwrap();
+    let coordinates = SrtCoordinates { x1: 100, x2: 500, y1: 400, y2: 450 };
+    assert_eq!(cue.meta, CueMeta::Srt { index: Some("1".to_string()), coordinates: Some(coordinates) });
+    
+    let ass = doc.convert(SubtitleFormat::Ass, &Default::default()).serialize();
+    assert!(ass.ends_with("Dialogue: 0,0:00:01.00,0:00:02.50,Default,,0,0,0,,{\\an5\\pos(300,425)}Hello\n"), "{}", ass);
+}
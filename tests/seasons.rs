@@ -0,0 +1,72 @@
+// Season maps between absolute and seasonal episode numbering
+
+use std::path::Path;
+
+use subsync::{mapped_episode_key, EpisodeKey, SeasonMap};
+
+#[test]
+fn parses_mapping_lines() {
+    let map = SeasonMap::parse("# Two seasons\nS01 1-12\n\n2: 13-24\nSeason 3 = 25-30\nseason4 31 - 40\n").unwrap();
+    assert_eq!(map, SeasonMap::parse("S1 1-12\ns2 13-24\nS03: 25-30\nS4=31-40").unwrap());
+    assert_eq!(map.to_seasonal(1), Some((1, 1)));
+    assert_eq!(map.to_seasonal(24), Some((2, 12)));
+    assert_eq!(map.to_seasonal(31), Some((4, 1)));
+    assert_eq!(map.to_seasonal(41), None);
+    assert_eq!(map.to_absolute(3, 6), Some(30));
+    assert_eq!(map.to_absolute(3, 7), None);
+    assert_eq!(map.to_absolute(3, 0), None);
+}
+
+#[test]
+fn rejects_invalid_mappings() {
+    // A bare range is not a season number followed by a range
+    assert!(SeasonMap::parse("105-124").is_err());
+    assert!(SeasonMap::parse("5 101-124").is_err());
+    assert!(SeasonMap::parse("S05 124-101").is_err());
+    assert!(SeasonMap::parse("S05 0-10").is_err());
+    assert!(SeasonMap::parse("S01 1-10\nS01 11-20").is_err());
+    assert!(SeasonMap::parse("S01 1-10\nS02 10-20").is_err());
+    assert!(SeasonMap::parse("# nothing\n").is_err());
+}
+
+#[test]
+fn builds_from_season_lengths() {
+    let map = SeasonMap::parse_lengths("25, 25,24").unwrap();
+    assert_eq!(map, SeasonMap::parse("S1 1-25\nS2 26-50\nS3 51-74").unwrap());
+    assert!(SeasonMap::parse_lengths("25,0").is_err());
+    assert!(SeasonMap::parse_lengths("25,x").is_err());
+    assert!(SeasonMap::from_lengths(&[u32::MAX, 2]).is_err());
+    assert!(SeasonMap::from_lengths(&[2, u32::MAX]).is_err());
+    assert!(SeasonMap::from_lengths(&[u32::MAX]).is_ok());
+}
+
+#[test]
+fn translates_between_numberings() {
+    let map = SeasonMap::parse("S05 101-124").unwrap();
+    assert_eq!(map.translate(EpisodeKey::new(None, 113)), Some(EpisodeKey::new(Some(5), 13)));
+    assert_eq!(map.translate(EpisodeKey::new(Some(5), 13)), Some(EpisodeKey::new(None, 113)));
+    assert_eq!(map.translate(EpisodeKey::range(None, 101, Some(102))), Some(EpisodeKey::range(Some(5), 1, Some(2))));
+    assert_eq!(map.translate(EpisodeKey::new(None, 125)), None);
+    assert_eq!(map.translate(EpisodeKey::new(Some(4), 1)), None);
+    
+    // A range across two seasons has no seasonal form
+    let map = SeasonMap::parse("S01 1-12\nS02 13-24").unwrap();
+    assert_eq!(map.translate(EpisodeKey::range(None, 12, Some(13))), None);
+}
+
+#[test]
+fn maps_episode_keys_of_files() {
+    let map = SeasonMap::parse("S05 101-124").unwrap();
+    let key = |path: &str| mapped_episode_key(Path::new(path), Some(&map));
+    assert_eq!(key("Show - 113.srt"), Some(EpisodeKey::new(Some(5), 13)));
+    assert_eq!(key("Show S02E03.srt"), Some(EpisodeKey::new(Some(2), 3)));
+    // Numbers outside the map stay absolute
+    assert_eq!(key("Show - 07.srt"), Some(EpisodeKey::new(None, 7)));
+    // Inside a season folder, either numbering gives the same episode
+    assert_eq!(key("Season 5/Show - 113.srt"), Some(EpisodeKey::new(Some(5), 13)));
+    assert_eq!(key("Season 5/Show - 13.srt"), Some(EpisodeKey::new(Some(5), 13)));
+    assert_eq!(key("Season 4/Show - 113.srt"), Some(EpisodeKey::new(Some(4), 113)));
+    assert_eq!(key("notes.txt"), None);
+    
+    assert_eq!(mapped_episode_key(Path::new("Show - 113.srt"), None), Some(EpisodeKey::new(None, 113)));
+}
@@ -0,0 +1,67 @@
+// The skipped-files report, and how --verbose shows the numbers read from each name
+
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+const CUES: &str = "1\n00:00:01,000 --> 00:00:02,000\nHello\n";
+
+fn skipped_lines(stdout: &str) -> Vec<&str> {
+    let report = stdout.split_once("Skipped (").map_or("", |(_, report)| report);
+    report.lines().skip(1).take_while(|line| !line.is_empty()).map(str::trim).collect()
+}
+
+#[test]
+fn lists_each_skipped_file_with_its_reason() {
+    let dir = common::folder_with("skipped", &[
+        ("Show - 01.mkv", ""),
+        ("Show - 01.srt", CUES),
+        ("notes.txt", "notes"),
+        ("reference.srt", CUES),
+        ("Extras.srt", CUES),
+    ]);
+    let reference = dir.join("reference.srt");
+    let output = common::run_on(&dir, &["--dry-run", "--reference", reference.to_str().unwrap()], &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        skipped_lines(&stdout),
+        ["Extras.srt: no episode number", "notes.txt: unsupported extension", "reference.srt: used as the reference"],
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn lists_unreadable_subfolders() {
+    let dir = common::folder_with("skipped-unreadable", &[("Show - 01.mkv", ""), ("Show - 01.srt", CUES), ("Locked/Show - 02.srt", CUES)]);
+    let locked = dir.join("Locked");
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+    // Permissions do not stop a superuser, so there is nothing to report then
+    let readable = fs::read_dir(&locked).is_ok();
+    let output = common::run_on(&dir, &["--dry-run", "--recursive"], &["1"]);
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+    if readable {
+        return;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines = skipped_lines(&stdout);
+    assert_eq!(lines.len(), 1, "{}", stdout);
+    assert!(lines[0].starts_with("Locked: unreadable folder: "), "{}", stdout);
+}
+
+#[test]
+fn verbose_shows_the_numbers_read_from_each_name() {
+    let dir = common::folder_with("skipped-verbose", &[
+        ("Show.S02E07.mkv", ""),
+        ("Show.2x07.srt", CUES),
+        ("[Group] Show - 07v2 [1080p].srt", CUES),
+    ]);
+    let output = common::run_on(&dir, &["--dry-run", "--verbose"], &["1"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Show.S02E07.mkv: 'S02E07' read as season 2, episode 7 (season and episode) → S02E07"), "{}", stdout);
+    assert!(stdout.contains("Show.2x07.srt: '2x07' read as season 2, episode 7 (season x episode) → S02E07"), "{}", stdout);
+    assert!(stdout.contains("Show - 07v2 [1080p].srt: '07v2' read as episode 7, version 2 (versioned number) → 7"), "{}", stdout);
+    // The rules are named without examples that could be taken for the values found
+    assert!(!stdout.contains("(S01E05)"), "{}", stdout);
+}
@@ -0,0 +1,59 @@
+// Language and flag tags at the end of subtitle file names
+
+use subsync::{Language, LanguageStyle, SubtitleFlag, SubtitleTags};
+
+/// (stem, suffix kept as found, as ISO 639-1, as ISO 639-2/B)
+const SUFFIXES: &[(&str, &str, &str, &str)] = &[
+    ("Show - 01.pt-BR.sdh", ".pt-BR.sdh", ".pt-BR.sdh", ".por-BR.sdh"),
+    ("Show - 01.pt_br.forced", ".pt_br.forced", ".pt-BR.forced", ".por-BR.forced"),
+    ("Movie Title.German", ".German", ".de", ".ger"),
+    ("Movie.Title.2019.jpn.default.forced", ".jpn.default.forced", ".ja.default.forced", ".jpn.default.forced"),
+    ("Show - 01.zh-hans", ".zh-hans", ".zh-Hans", ".chi-Hans"),
+    ("Show - 01.es-419.cc", ".es-419.cc", ".es-419.cc", ".spa-419.cc"),
+    ("Show - 01.deu", ".deu", ".de", ".ger"),
+    ("Show.S01E05.IT", ".IT", ".it", ".ita"),
+    ("The.Movie.It.forced", ".It.forced", ".it.forced", ".ita.forced"),
+    ("The.Movie.it", ".it", ".it", ".ita"),
+];
+
+#[test]
+fn reads_and_rewrites_tags() {
+    for &(stem, keep, alpha2, alpha3) in SUFFIXES {
+        let tags = SubtitleTags::parse(stem);
+        assert_eq!(tags.suffix(LanguageStyle::Keep), keep, "{}", stem);
+        assert_eq!(tags.suffix(LanguageStyle::Alpha2), alpha2, "{}", stem);
+        assert_eq!(tags.suffix(LanguageStyle::Alpha3), alpha3, "{}", stem);
+    }
+}
+
+#[test]
+fn keeps_flag_order() {
+    let tags = SubtitleTags::parse("Show - 01.pt-BR.sdh");
+    let language = tags.language.unwrap();
+    assert_eq!((language.alpha2, language.region.as_deref()), ("pt", Some("BR")));
+    assert_eq!(tags.flags, [SubtitleFlag::Sdh]);
+    assert_eq!(SubtitleTags::parse("Show.en.forced.sdh").flags, [SubtitleFlag::Forced, SubtitleFlag::Sdh]);
+}
+
+#[test]
+fn finds_no_tags_in_plain_names() {
+    for stem in ["Show - 01", "Movie Title (2019)", "Show.S01E01.1080p.WEB-DL", "forced", "en", "Show - 01.v2", "Show.Dark"] {
+        assert!(SubtitleTags::parse(stem).is_empty(), "{}", stem);
+        assert_eq!(SubtitleTags::parse(stem).suffix(LanguageStyle::Alpha2), "", "{}", stem);
+    }
+    // Capitalised two-letter words ending a title are not language codes
+    for stem in ["The.Movie.It", "Show.No", "What.Is", "Show.He", "Say.Hi", "The.ID", "Show.DE"] {
+        assert!(SubtitleTags::parse(stem).is_empty(), "{}", stem);
+    }
+    // Only the last language is a tag; one before it is part of the name
+    let tags = SubtitleTags::parse("Show.French.en");
+    assert_eq!(tags.suffix(LanguageStyle::Keep), ".en");
+    assert_eq!(Language::parse("xx-BR"), None);
+}
+
+#[test]
+fn reads_track_names() {
+    assert_eq!(SubtitleTags::parse_track("3_English_SDH").suffix(LanguageStyle::Alpha2), ".en.sdh");
+    assert_eq!(SubtitleTags::parse_track("English").suffix(LanguageStyle::Alpha3), ".eng");
+    assert!(SubtitleTags::parse_track("2_Signs_Songs").is_empty());
+}
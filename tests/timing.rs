@@ -0,0 +1,66 @@
+// Exact time scales: parsing ratios and frame rates, and rounding when applying them
+
+use subsync::timing::{fps_scale, parse_fps};
+use subsync::{LinearMap, Ratio};
+
+fn ratio(num: i64, den: i64) -> Ratio {
+    Ratio::new(num, den).unwrap()
+}
+
+#[test]
+fn parses_decimals_and_fractions() {
+    assert_eq!(Ratio::parse("1.0427"), Some(ratio(10427, 10000)));
+    assert_eq!(Ratio::parse("25/24"), Some(ratio(25, 24)));
+    assert_eq!(Ratio::parse(" 24000 / 1001 "), Some(ratio(24000, 1001)));
+    assert_eq!(Ratio::parse("50/2"), Some(ratio(25, 1)));
+    assert_eq!(Ratio::parse("-.5"), Some(ratio(-1, 2)));
+    assert_eq!(ratio(48, -2), ratio(-24, 1));
+    for invalid in ["", ".", "1/0", "1.5.2", "1e3", "0.1234567890123"] {
+        assert_eq!(Ratio::parse(invalid), None, "{}", invalid);
+    }
+}
+
+#[test]
+fn reads_ntsc_rates_exactly() {
+    assert_eq!(parse_fps("23.976"), Some(ratio(24000, 1001)));
+    assert_eq!(parse_fps("24000/1001"), parse_fps("23.976"));
+    assert_eq!(parse_fps("23.98"), parse_fps("23.976"));
+    assert_eq!(parse_fps("29.97"), Some(ratio(30000, 1001)));
+    assert_eq!(parse_fps("59.94"), Some(ratio(60000, 1001)));
+    assert_eq!(parse_fps("25"), Some(ratio(25, 1)));
+    assert_eq!(parse_fps("23.9"), Some(ratio(239, 10)));
+    for invalid in ["0", "-25", "fast", "25/0"] {
+        assert_eq!(parse_fps(invalid), None, "{}", invalid);
+    }
+}
+
+#[test]
+fn converts_frame_rates_without_drift() {
+    let ntsc = parse_fps("23.976").unwrap();
+    let pal = parse_fps("25").unwrap();
+    let scale = fps_scale(ntsc, pal).unwrap();
+    assert_eq!(scale, ratio(960, 1001));
+    
+    // 02:00:00,000 * 960/1001 = 6905094.905 ms; the float 0.95904 would give 6905088
+    assert_eq!(scale.apply(7_200_000), 6_905_095);
+    assert_eq!(scale.apply(7_199_999), 6_905_094);
+    let back = fps_scale(pal, ntsc).unwrap();
+    assert_eq!(back.apply(7_200_000), 7_507_500);
+    assert_eq!(scale.checked_mul(back), Some(Ratio::ONE));
+    
+    // Each time is scaled from its own value, so the last cue of a long file is as exact as the first
+    let map = LinearMap::new(scale, 1_000);
+    assert_eq!(map.apply(7_200_000), 6_906_095);
+    assert_eq!(map.apply(0), 1_000);
+}
+
+#[test]
+fn rounds_halves_up() {
+    let half = ratio(1, 2);
+    assert_eq!(half.apply(3), 2);
+    assert_eq!(half.apply(-3), -1);
+    assert_eq!(half.apply(5), 3);
+    assert_eq!(ratio(2, 3).apply(1), 1);
+    assert_eq!(ratio(1, 3).apply(1), 0);
+    assert_eq!(Ratio::ONE.apply(i64::MAX / 2), i64::MAX / 2);
+}